@@ -1,16 +1,22 @@
-use window_framework::{Canvas, CoordinateSystem, World, WorldConfig};
+use window_framework::{Canvas, CoordinateSystem, InputState, KeyCode, World, WorldConfig};
 
 const RESOLUTION_WIDTH: u32 = 320;
 const RESOLUTION_HEIGHT: u32 = 240;
 const WIDTH: u32 = RESOLUTION_WIDTH / 8;
 const HEIGHT: u32 = RESOLUTION_HEIGHT / 8;
 const BOX_SIZE: i16 = 8;
+/// Number of update steps per pulse of the box's highlight
+const PULSE_STEPS: f32 = 60.0;
 
 struct BouncingBox {
     box_x: i16,
     box_y: i16,
     velocity_x: i16,
     velocity_y: i16,
+    show_grid: bool,
+    speed: u32,
+    marquee_phase: u32,
+    pulse_phase: f32,
 }
 
 impl World for BouncingBox {
@@ -20,6 +26,10 @@ impl World for BouncingBox {
             box_y: 16,
             velocity_x: 1,
             velocity_y: 1,
+            show_grid: true,
+            speed: 1,
+            marquee_phase: 0,
+            pulse_phase: 0.0,
         }
     }
 
@@ -36,6 +46,24 @@ impl World for BouncingBox {
         )
     }
 
+    fn show_grid(&self) -> Option<bool> {
+        Some(self.show_grid)
+    }
+
+    fn updates_per_frame(&self) -> Option<u32> {
+        Some(self.speed)
+    }
+
+    fn handle_input(&mut self, input: &InputState) {
+        if input.is_key_just_pressed(KeyCode::KeyG) {
+            self.show_grid = !self.show_grid;
+        }
+        // Cycle through 1x, 2x, 4x and 8x speed
+        if input.is_key_just_pressed(KeyCode::KeyF) {
+            self.speed = if self.speed >= 8 { 1 } else { self.speed * 2 };
+        }
+    }
+
     fn update(&mut self) {
         if self.box_x <= 0 || self.box_x + BOX_SIZE > WIDTH as i16 {
             self.velocity_x *= -1;
@@ -46,6 +74,8 @@ impl World for BouncingBox {
 
         self.box_x += self.velocity_x;
         self.box_y += self.velocity_y;
+        self.marquee_phase = self.marquee_phase.wrapping_add(1);
+        self.pulse_phase = (self.pulse_phase + 1.0 / PULSE_STEPS) % 1.0;
     }
 
     fn draw(&self, canvas: &mut Canvas) {
@@ -60,6 +90,28 @@ impl World for BouncingBox {
             BOX_SIZE as u32,
             (0x5e, 0x48, 0xe8, 0xff),
         );
+
+        // Pulsing highlight along the box's edge
+        canvas.draw_pulse_highlight(
+            self.box_x as i32,
+            self.box_y as i32,
+            BOX_SIZE as u32,
+            BOX_SIZE as u32,
+            (0xff, 0xd0, 0x40, 0xff),
+            self.pulse_phase,
+        );
+
+        // Marching-ants selection marquee just outside the box
+        canvas.draw_rect_dashed(
+            self.box_x as i32 - 1,
+            self.box_y as i32 - 1,
+            BOX_SIZE as u32 + 2,
+            BOX_SIZE as u32 + 2,
+            (0xff, 0xff, 0xff, 0xff),
+            2,
+            2,
+            self.marquee_phase,
+        );
     }
 }
 
@@ -0,0 +1,169 @@
+use window_framework::{Canvas, Color, CoordinateSystem, InputState, KeyCode, World, WorldConfig};
+
+const RESOLUTION_WIDTH: u32 = 640;
+const RESOLUTION_HEIGHT: u32 = 480;
+const WIDTH: u32 = RESOLUTION_WIDTH / 4;
+const HEIGHT: u32 = RESOLUTION_HEIGHT / 4;
+const SWATCH_WIDTH: u32 = 12;
+const SWATCH_HEIGHT: u32 = 8;
+const BACKGROUND: Color = (0xf4, 0xf1, 0xe8, 0xff);
+const PALETTE: [Color; 8] = [
+    (0x20, 0x20, 0x20, 0xff),
+    (0xe0, 0x3a, 0x3a, 0xff),
+    (0xf0, 0x90, 0x30, 0xff),
+    (0xf0, 0xd0, 0x40, 0xff),
+    (0x40, 0xa8, 0x50, 0xff),
+    (0x38, 0x78, 0xd8, 0xff),
+    (0x8a, 0x50, 0xc8, 0xff),
+    (0xff, 0xff, 0xff, 0xff),
+];
+const BRUSH_KEYS: [KeyCode; 8] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+];
+
+/// A straight piece of a brush stroke between two logical pixels
+struct Segment {
+    from: (i32, i32),
+    to: (i32, i32),
+    color: Color,
+}
+
+struct Paint {
+    segments: Vec<Segment>,
+    color: usize,
+    /// Pixel under the cursor this frame, if any
+    cursor: Option<(i32, i32)>,
+    /// Whether the left button is held down over the drawing area
+    painting: bool,
+    /// End of the stroke being drawn, where the next segment starts
+    last_point: Option<(i32, i32)>,
+    clear_requested: bool,
+}
+
+impl Paint {
+    /// Index of the palette swatch at a pixel, if it is inside the palette bar
+    fn swatch_at(x: i32, y: i32) -> Option<usize> {
+        if y < 0 || y >= SWATCH_HEIGHT as i32 || x < 0 {
+            return None;
+        }
+        let index = x as usize / SWATCH_WIDTH as usize;
+        (index < PALETTE.len()).then_some(index)
+    }
+}
+
+impl World for Paint {
+    fn new() -> Self {
+        Self {
+            segments: Vec::new(),
+            color: 0,
+            cursor: None,
+            painting: false,
+            last_point: None,
+            clear_requested: false,
+        }
+    }
+
+    fn config() -> WorldConfig {
+        WorldConfig::new(
+            RESOLUTION_WIDTH,
+            RESOLUTION_HEIGHT,
+            "Paint",
+            CoordinateSystem::TopLeft,
+            Some(WIDTH),
+            Some(HEIGHT),
+            false,
+            BACKGROUND,
+        )
+    }
+
+    fn handle_input(&mut self, input: &InputState) {
+        self.cursor = input.picked_pixel();
+
+        // Number keys pick a color, C clears the drawing
+        for (index, &key) in BRUSH_KEYS.iter().enumerate() {
+            if input.is_key_just_pressed(key) {
+                self.color = index;
+            }
+        }
+        if input.is_key_just_pressed(KeyCode::KeyC) {
+            self.clear_requested = true;
+        }
+
+        // Clicking a swatch picks its color instead of starting a stroke
+        if input.is_left_mouse_just_pressed() {
+            if let Some(index) = self.cursor.and_then(|(x, y)| Self::swatch_at(x, y)) {
+                self.color = index;
+                self.painting = false;
+                return;
+            }
+        }
+        self.painting = input.is_left_mouse_pressed()
+            && (self.painting || input.is_left_mouse_just_pressed());
+    }
+
+    fn update(&mut self) {
+        if self.clear_requested {
+            self.segments.clear();
+            self.clear_requested = false;
+        }
+
+        match (self.painting, self.cursor) {
+            // Holding the cursor still adds nothing; only a new stroke's first point is a dot
+            (true, Some(point)) if self.last_point != Some(point) => {
+                let from = self.last_point.unwrap_or(point);
+                self.segments.push(Segment {
+                    from,
+                    to: point,
+                    color: PALETTE[self.color],
+                });
+                self.last_point = Some(point);
+            }
+            (true, Some(_)) => {}
+            // Lifting the button or leaving the window ends the stroke
+            _ => self.last_point = None,
+        }
+    }
+
+    fn draw(&self, canvas: &mut Canvas) {
+        canvas.clear(BACKGROUND);
+
+        for segment in &self.segments {
+            canvas.draw_line(
+                segment.from.0,
+                segment.from.1,
+                segment.to.0,
+                segment.to.1,
+                segment.color,
+            );
+        }
+
+        // Palette bar along the top, with a black underline below the selected color
+        for (index, &color) in PALETTE.iter().enumerate() {
+            let x = (index as u32 * SWATCH_WIDTH) as i32;
+            canvas.fill_rect(x, 0, SWATCH_WIDTH, SWATCH_HEIGHT, color);
+            if index == self.color {
+                canvas.fill_rect(x, SWATCH_HEIGHT as i32, SWATCH_WIDTH, 2, (0, 0, 0, 0xff));
+            }
+        }
+
+        // Small crosshair in the brush color under the cursor
+        if let Some((x, y)) = self.cursor {
+            let color = PALETTE[self.color];
+            canvas.draw_line(x - 2, y, x - 1, y, color);
+            canvas.draw_line(x + 1, y, x + 2, y, color);
+            canvas.draw_line(x, y - 2, x, y - 1, color);
+            canvas.draw_line(x, y + 1, x, y + 2, color);
+        }
+    }
+}
+
+fn main() {
+    window_framework::run::<Paint>().unwrap();
+}
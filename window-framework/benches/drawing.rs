@@ -0,0 +1,86 @@
+//! Drawing benchmarks over a window-less canvas
+//!
+//! Each case draws into a plain buffer through `Canvas::new`, the same constructor the window
+//! and `OwnedCanvas` use, and reports the mean time per iteration. Pass a substring as the
+//! first argument to run only the matching cases, e.g. `cargo bench -- fill_rect`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use window_framework::{Canvas, CoordinateSystem};
+
+/// How long each case is timed for, after one warm-up pass of the same length
+const MEASURE_TIME: Duration = Duration::from_millis(500);
+
+/// Representative (physical width, physical height, logical width, logical height) sizes
+const SIZES: [(u32, u32, u32, u32); 3] = [
+    (320, 240, 40, 30),
+    (640, 480, 640, 480),
+    (1920, 1080, 480, 270),
+];
+
+const COLOR: (u8, u8, u8, u8) = (0x5e, 0x48, 0xe8, 0xff);
+
+/// Time `f` for `MEASURE_TIME` and print the mean time per call
+fn bench(filter: Option<&str>, name: &str, mut f: impl FnMut()) {
+    if filter.is_some_and(|filter| !name.contains(filter)) {
+        return;
+    }
+    let mut run = || {
+        let start = Instant::now();
+        let mut iterations = 0u32;
+        while start.elapsed() < MEASURE_TIME {
+            f();
+            iterations += 1;
+        }
+        start.elapsed() / iterations
+    };
+    run();
+    let per_iteration = run();
+    println!("{name:40} {:>12.2} µs", per_iteration.as_secs_f64() * 1e6);
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` to harness-less targets, which isn't a filter
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let filter = filter.as_deref();
+
+    for (physical_width, physical_height, logical_width, logical_height) in SIZES {
+        let mut frame = vec![0; (physical_width * physical_height * 4) as usize];
+        let mut canvas = Canvas::new(
+            &mut frame,
+            physical_width,
+            physical_height,
+            logical_width,
+            logical_height,
+            CoordinateSystem::TopLeft,
+            false,
+            (0, 0, 0, 0),
+        );
+        let size = format!("{physical_width}x{physical_height}/{logical_width}x{logical_height}");
+        let (w, h) = (logical_width as i32, logical_height as i32);
+
+        bench(filter, &format!("clear {size}"), || {
+            black_box(&mut canvas).clear(COLOR);
+        });
+        bench(filter, &format!("fill_rect full {size}"), || {
+            black_box(&mut canvas).fill_rect(0, 0, logical_width, logical_height, COLOR);
+        });
+        bench(filter, &format!("fill_rect quarter {size}"), || {
+            black_box(&mut canvas).fill_rect(w / 4, h / 4, logical_width / 2, logical_height / 2, COLOR);
+        });
+        bench(filter, &format!("set_pixel all {size}"), || {
+            let canvas = black_box(&mut canvas);
+            for y in 0..h {
+                for x in 0..w {
+                    canvas.set_pixel(x, y, COLOR);
+                }
+            }
+        });
+        bench(filter, &format!("draw_line diagonals {size}"), || {
+            let canvas = black_box(&mut canvas);
+            canvas.draw_line(0, 0, w - 1, h - 1, COLOR);
+            canvas.draw_line(0, h - 1, w - 1, 0, COLOR);
+        });
+    }
+}
@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::headless::render_frames;
+use crate::png::{header, image_data, write_chunk, SIGNATURE};
+use crate::world::World;
+
+/// Render a world headlessly and encode the frames into an animated PNG file
+///
+/// Every frame is stored in full at the physical size from `W::config()` with its alpha, so
+/// colors survive exactly, and is rendered (see `render_frames`) and shown for `1 / fps` seconds
+/// (`fps` is clamped to 1-65535).
+/// Each frame replaces the previous one instead of being blended over it, and the animation
+/// loops forever. Like `encode_png` the image data is uncompressed, so files grow quickly with
+/// size and frame count. Frames are written out as they are rendered.
+///
+/// Returns an `InvalidInput` error if `frames` is 0, and any error from writing the file
+pub fn render_to_apng<W: World>(frames: u32, fps: u32, path: impl AsRef<Path>) -> io::Result<()> {
+    if frames == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "an APNG needs at least one frame"));
+    }
+    let delay_den = fps.clamp(1, u16::MAX as u32) as u16;
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(&SIGNATURE)?;
+
+    // fcTL and fdAT chunks share one sequence counter
+    let mut sequence = 0u32;
+    let mut chunk = Vec::new();
+    render_frames::<W, io::Error>(frames, fps, |frame, width, height| {
+        chunk.clear();
+        if sequence == 0 {
+            write_chunk(&mut chunk, b"IHDR", &header(width, height, frame)?);
+            let mut animation = Vec::with_capacity(8);
+            animation.extend_from_slice(&frames.to_be_bytes());
+            // Zero plays means loop forever
+            animation.extend_from_slice(&0u32.to_be_bytes());
+            write_chunk(&mut chunk, b"acTL", &animation);
+        }
+
+        let mut control = Vec::with_capacity(26);
+        control.extend_from_slice(&sequence.to_be_bytes());
+        control.extend_from_slice(&width.to_be_bytes());
+        control.extend_from_slice(&height.to_be_bytes());
+        // Offset 0,0, a delay of 1/fps seconds, no disposal and source blending
+        control.extend_from_slice(&[0; 8]);
+        control.extend_from_slice(&1u16.to_be_bytes());
+        control.extend_from_slice(&delay_den.to_be_bytes());
+        control.extend_from_slice(&[0, 0]);
+        write_chunk(&mut chunk, b"fcTL", &control);
+        sequence += 1;
+
+        // The first frame doubles as the static image for viewers without APNG support
+        if sequence == 1 {
+            write_chunk(&mut chunk, b"IDAT", &image_data(width, frame));
+        } else {
+            let mut data = sequence.to_be_bytes().to_vec();
+            data.extend_from_slice(&image_data(width, frame));
+            write_chunk(&mut chunk, b"fdAT", &data);
+            sequence += 1;
+        }
+        file.write_all(&chunk)
+    })?;
+
+    chunk.clear();
+    write_chunk(&mut chunk, b"IEND", &[]);
+    file.write_all(&chunk)?;
+    file.flush()
+}
@@ -1,17 +1,29 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use error_iter::ErrorIter as _;
-use log::error;
-use pixels::{Pixels, SurfaceTexture};
+use log::{error, warn};
+use pixels::{wgpu, Pixels, PixelsBuilder, SurfaceTexture};
 use winit::{
     application::ApplicationHandler,
+    dpi::{PhysicalPosition, PhysicalSize},
     event::WindowEvent,
-    event_loop::ActiveEventLoop,
-    keyboard::{KeyCode, PhysicalKey},
+    event_loop::{ActiveEventLoop, ControlFlow},
+    keyboard::{Key, KeyCode, PhysicalKey},
     window::{Window, WindowAttributes, WindowId},
 };
 
-use crate::{canvas::{Canvas, CoordinateSystem}, input::InputState, world::World};
+use crate::{
+    canvas::{Canvas, Color, CoordinateSystem, GridStyle},
+    event::{FrameworkEvent, MouseButton},
+    input::InputState,
+    timing::{Clock, FrameTimer, TargetFps},
+    ui::Ui,
+    world::{FrameSink, StartContext, World, WorldConfig},
+};
+#[cfg(feature = "hot-reload")]
+use crate::hot_reload::ConfigWatcher;
 
 pub struct App<W: World> {
     window: Option<Arc<Window>>,
@@ -23,11 +35,50 @@ pub struct App<W: World> {
     pixel_grid_width: u32,
     pixel_grid_height: u32,
     coordinate_system: CoordinateSystem,
+    grid_color: Color,
+    grid_style: GridStyle,
+    /// The `show_grid` passed to `new`, or a reloaded config's; used unless `World::show_grid`
+    /// overrides it
     show_grid: bool,
-    grid_color: (u8, u8, u8, u8),
+    /// `WorldConfig::updates_per_frame`, used unless `World::updates_per_frame` overrides it
+    updates_per_frame: u32,
+    frame_timer: FrameTimer,
+    clock: Clock,
+    target_fps: TargetFps,
+    pixel_aspect: f32,
+    smooth_upscale: bool,
+    /// Unsmoothed picture the world draws into while `smooth_upscale` is on, empty otherwise
+    scene: Vec<u8>,
+    frame_sink: Option<FrameSink>,
+    frame_budget: Option<Duration>,
+    opaque: bool,
+    update_while_minimized: bool,
+    /// Whether the window is minimized or hidden, in which case nothing is drawn
+    minimized: bool,
+    /// When the next simulation step is due while minimized with `update_while_minimized`
+    next_minimized_step: Instant,
+    /// When the next frame is due at the target frame rate
+    next_frame: Instant,
+    /// Whether a redraw has been requested and not yet delivered
+    redraw_pending: bool,
+    /// Whether the next frame must be uploaded and presented even if nothing draws into it,
+    /// because the surface or the settings it was drawn with changed
+    force_render: bool,
+    /// Whether the grid was drawn on the last presented frame
+    grid_shown: bool,
+    /// Logical key each held physical key produced when it went down, so releases remove the
+    /// right entry even if the modifiers changed in between
+    held_logical_keys: HashMap<PhysicalKey, Key>,
+    #[cfg(feature = "hot-reload")]
+    config_watcher: Option<ConfigWatcher>,
 }
 
 impl<W: World> App<W> {
+    /// Create an app with the given buffer, grid and coordinate settings
+    ///
+    /// `show_grid` and `grid_color` take precedence over `W::config()` when the window opens;
+    /// the remaining settings come from the config, and a reloaded config file replaces all of
+    /// them
     pub fn new(
         width: u32,
         height: u32,
@@ -35,7 +86,7 @@ impl<W: World> App<W> {
         pixel_grid_height: u32,
         coordinate_system: CoordinateSystem,
         show_grid: bool,
-        grid_color: (u8, u8, u8, u8),
+        grid_color: Color,
     ) -> Self {
         Self {
             window: None,
@@ -47,29 +98,198 @@ impl<W: World> App<W> {
             pixel_grid_width,
             pixel_grid_height,
             coordinate_system,
-            show_grid,
             grid_color,
+            grid_style: GridStyle::Lines,
+            show_grid,
+            updates_per_frame: 1,
+            frame_timer: FrameTimer::new(60),
+            clock: Clock::new(None),
+            target_fps: TargetFps::Fixed(60),
+            pixel_aspect: 1.0,
+            smooth_upscale: false,
+            scene: Vec::new(),
+            frame_sink: None,
+            frame_budget: None,
+            opaque: true,
+            update_while_minimized: false,
+            minimized: false,
+            next_minimized_step: Instant::now(),
+            next_frame: Instant::now(),
+            redraw_pending: false,
+            force_render: true,
+            grid_shown: false,
+            held_logical_keys: HashMap::new(),
+            #[cfg(feature = "hot-reload")]
+            config_watcher: None,
+        }
+    }
+
+    /// Take over the settings from a config that can change while running
+    fn apply_config(&mut self, config: &WorldConfig) {
+        self.target_fps = config.target_fps;
+        self.update_target_fps();
+        self.pixel_aspect = config.pixel_aspect;
+        self.smooth_upscale = config.smooth_upscale;
+        self.grid_color = config.grid_color;
+        self.grid_style = config.grid_style;
+        self.show_grid = config.show_grid;
+        self.updates_per_frame = config.updates_per_frame;
+        self.opaque = config.opaque && !config.transparent;
+        self.force_render = true;
+        self.update_while_minimized = config.update_while_minimized;
+        self.frame_budget = config
+            .frame_budget_ms
+            .and_then(|budget_ms| Duration::try_from_secs_f32(budget_ms / 1000.0).ok());
+        if let Some(window) = self.window.as_ref() {
+            window.set_title(&config.title);
+        }
+    }
+
+    /// Reload the config file if it changed, applying it and passing it to the world
+    #[cfg(feature = "hot-reload")]
+    fn poll_config_file(&mut self) {
+        let Some(config) = self.config_watcher.as_mut().and_then(|watcher| watcher.poll()) else {
+            return;
+        };
+        self.apply_config(&config);
+        if let Some(world) = self.world.as_mut() {
+            world.on_config_reload(&config);
+        }
+    }
+
+    /// Translate a window event into the event reported to `World::on_event`, if it is one
+    fn framework_event(&self, event: &WindowEvent) -> Option<FrameworkEvent> {
+        let mouse_button = |button: &winit::event::MouseButton| match button {
+            winit::event::MouseButton::Left => Some(MouseButton::Left),
+            winit::event::MouseButton::Middle => Some(MouseButton::Middle),
+            winit::event::MouseButton::Right => Some(MouseButton::Right),
+            _ => None,
+        };
+        match event {
+            WindowEvent::CloseRequested => Some(FrameworkEvent::CloseRequested),
+            WindowEvent::Resized(size) => Some(FrameworkEvent::Resized {
+                width: size.width,
+                height: size.height,
+            }),
+            WindowEvent::Focused(focused) => Some(FrameworkEvent::FocusChanged(*focused)),
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                Some(FrameworkEvent::ScaleFactorChanged(*scale_factor))
+            }
+            WindowEvent::KeyboardInput { event, .. } if !event.repeat => match event.physical_key {
+                PhysicalKey::Code(key_code) if event.state.is_pressed() => {
+                    Some(FrameworkEvent::KeyPressed(key_code))
+                }
+                PhysicalKey::Code(key_code) => Some(FrameworkEvent::KeyReleased(key_code)),
+                PhysicalKey::Unidentified(_) => None,
+            },
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = mouse_button(button)?;
+                if state.is_pressed() {
+                    Some(FrameworkEvent::MouseClicked {
+                        button,
+                        position: self.input.mouse_position,
+                    })
+                } else {
+                    Some(FrameworkEvent::MouseReleased(button))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Pass an event to the world's `on_event` hook, once the world exists
+    fn emit(&mut self, event: &FrameworkEvent) {
+        if let Some(world) = self.world.as_mut() {
+            world.on_event(event);
         }
     }
+
+    /// Log a failed operation and report it to the world
+    fn report_error<E: std::error::Error + 'static>(&mut self, method_name: &str, err: E) {
+        let message = err.to_string();
+        log_error(method_name, err);
+        self.emit(&FrameworkEvent::Error {
+            operation: method_name.to_string(),
+            message,
+        });
+    }
+
+    /// Enter or leave the minimized state, pausing drawing and switching how the loop is driven
+    ///
+    /// While minimized the loop waits between simulation steps (see `about_to_wait`) instead of
+    /// between frames, and on restore the next frame is drawn right away
+    fn set_minimized(&mut self, event_loop: &ActiveEventLoop, minimized: bool) {
+        if minimized == self.minimized {
+            return;
+        }
+        self.minimized = minimized;
+        self.emit(&FrameworkEvent::Minimized(minimized));
+        self.frame_timer.pause();
+        if minimized {
+            event_loop.set_control_flow(ControlFlow::Wait);
+        } else {
+            self.next_frame = Instant::now();
+            self.force_render = true;
+            self.request_redraw();
+        }
+    }
+
+    /// Ask for the next frame to be drawn, unless a request is already on its way
+    fn request_redraw(&mut self) {
+        if let Some(window) = self.window.as_ref() {
+            if !self.redraw_pending {
+                window.request_redraw();
+                self.redraw_pending = true;
+            }
+        }
+    }
+
+    /// Resolve the configured target frame rate against the window's current monitor
+    fn update_target_fps(&mut self) {
+        let refresh_rate = self
+            .window
+            .as_ref()
+            .and_then(|window| window.current_monitor())
+            .and_then(|monitor| monitor.refresh_rate_millihertz());
+        self.frame_timer.set_target_fps(self.target_fps.resolve(refresh_rate));
+    }
 }
 
 impl<W: World> ApplicationHandler for App<W> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
-        let window_attrs = WindowAttributes::default()
-            .with_title(W::config().title);
+        let config = W::config();
+        let mut window_attrs = WindowAttributes::default()
+            .with_title(config.title.clone())
+            .with_transparent(config.transparent);
+        if let Some((x, y)) = config.position {
+            window_attrs = window_attrs.with_position(PhysicalPosition::new(x, y));
+        }
+        if let Some((width, height)) = config.window_size {
+            window_attrs = window_attrs.with_inner_size(PhysicalSize::new(width, height));
+        }
 
         let window = event_loop
             .create_window(window_attrs)
             .unwrap();
         let window = Arc::new(window);
         self.window = Some(window.clone());
+        self.input.scale_factor = window.scale_factor();
+        // The grid settings given to `new` win over the config until a reload replaces them
+        let (show_grid, grid_color) = (self.show_grid, self.grid_color);
+        self.apply_config(&config);
+        (self.show_grid, self.grid_color) = (show_grid, grid_color);
 
         self.pixels = {
             let (window_width, window_height) = window.inner_size().into();
             let surface_texture = SurfaceTexture::new(window_width, window_height, window.clone());
-            match Pixels::new(self.width, self.height, surface_texture) {
+            let mut builder = PixelsBuilder::new(self.width, self.height, surface_texture);
+            if config.transparent {
+                builder = builder.clear_color(wgpu::Color::TRANSPARENT);
+            }
+            match builder.build() {
                 Ok(pixels) => {
                     window.request_redraw();
+                    self.redraw_pending = true;
                     Some(pixels)
                 }
                 Err(err) => {
@@ -80,7 +300,54 @@ impl<W: World> ApplicationHandler for App<W> {
             }
         };
 
-        self.world = Some(W::new());
+        let mut world = W::new();
+        world.on_start(&StartContext {
+            monitor_size: primary_monitor_size(event_loop),
+        });
+        self.frame_sink = world.frame_sink();
+        self.clock = Clock::new(world.time_source());
+        self.world = Some(world);
+
+        #[cfg(feature = "hot-reload")]
+        {
+            self.config_watcher = config
+                .config_file
+                .clone()
+                .map(|path| ConfigWatcher::new(path, config.clone()));
+            self.poll_config_file();
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.minimized {
+            // Sleep until the next frame is due instead of drawing as fast as possible
+            if Instant::now() >= self.next_frame {
+                self.request_redraw();
+            }
+            let control_flow = if self.redraw_pending {
+                ControlFlow::Wait
+            } else {
+                ControlFlow::WaitUntil(self.next_frame)
+            };
+            event_loop.set_control_flow(control_flow);
+            return;
+        }
+        if !self.update_while_minimized {
+            return;
+        }
+        // Redraws are suspended, so step the simulation from here at the target rate
+        let now = Instant::now();
+        if now >= self.next_minimized_step {
+            if let Some(world) = self.world.as_mut() {
+                world.handle_input(&self.input);
+                for _ in 0..world.updates_per_frame().unwrap_or(self.updates_per_frame).max(1) {
+                    world.update();
+                }
+                self.input.end_frame();
+            }
+            self.next_minimized_step = now + self.frame_timer.target_interval();
+        }
+        event_loop.set_control_flow(ControlFlow::WaitUntil(self.next_minimized_step));
     }
 
     fn window_event(
@@ -89,78 +356,320 @@ impl<W: World> ApplicationHandler for App<W> {
         _window_id: WindowId,
         event: WindowEvent,
     ) {
+        if let Some(framework_event) = self.framework_event(&event) {
+            self.emit(&framework_event);
+        }
+
         match event {
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
+            WindowEvent::RedrawRequested if self.minimized => self.redraw_pending = false,
             WindowEvent::RedrawRequested => {
+                // A redraw we didn't ask for comes from the system, which wants the window repainted
+                if !self.redraw_pending {
+                    self.force_render = true;
+                }
+                self.redraw_pending = false;
+                // Schedule from the previous deadline so the rate doesn't drift, but never into
+                // the past, so a slow frame doesn't cause a burst of catch-up frames
+                let now = Instant::now();
+                self.next_frame = (self.next_frame + self.frame_timer.target_interval()).max(now);
+                #[cfg(feature = "hot-reload")]
+                self.poll_config_file();
                 if let Some(world) = self.world.as_mut() {
+                    let stats = self.frame_timer.tick(self.clock.now());
+                    world.on_frame_stats(&stats);
                     world.handle_input(&self.input);
-                    world.update();
+                    let update_start = Instant::now();
+                    for _ in 0..world.updates_per_frame().unwrap_or(self.updates_per_frame).max(1) {
+                        world.update();
+                    }
+                    let update_time = update_start.elapsed();
+
+                    let draw_start = Instant::now();
+                    let layout = FrameLayout {
+                        width: self.width,
+                        height: self.height,
+                        pixel_grid_width: self.pixel_grid_width,
+                        pixel_grid_height: self.pixel_grid_height,
+                        coordinate_system: self.coordinate_system,
+                        show_grid: world.show_grid().unwrap_or(self.show_grid),
+                        grid_color: self.grid_color,
+                        grid_style: self.grid_style,
+                        pixel_aspect: self.pixel_aspect,
+                    };
                     let frame = self.pixels.as_mut().unwrap().frame_mut();
-                    let mut canvas = Canvas::new(
-                        frame,
-                        self.width,
-                        self.height,
-                        self.pixel_grid_width,
-                        self.pixel_grid_height,
-                        self.coordinate_system,
-                        self.show_grid,
-                        self.grid_color,
-                    );
-                    world.draw(&mut canvas);
-                    canvas.draw_grid();
-                    if let Err(err) = self.pixels.as_ref().unwrap().render() {
-                        log_error("pixels.render", err);
-                        event_loop.exit();
+                    let show_grid = layout.show_grid;
+                    let frame_bytes = frame.len();
+                    let drawn = draw_frame(world, &layout, frame, &mut self.scene, self.smooth_upscale, &mut self.input);
+                    // The frame buffer counts twice, for the GPU texture it is uploaded to
+                    self.input.buffer_size_bytes = frame_bytes * 2
+                        + self.scene.len()
+                        + self.input.ids.len() * std::mem::size_of::<u32>()
+                        + world.buffer_size_bytes();
+                    let draw_time = draw_start.elapsed();
+                    self.frame_timer.record_phases(update_time, draw_time);
+                    if let Some(budget) = self.frame_budget {
+                        warn_if_over_budget("update", update_time, budget);
+                        warn_if_over_budget("draw", draw_time, budget);
+                    }
+                    self.input.end_frame();
+                    // Leave the GPU alone when the frame is exactly what is already on screen
+                    let render = drawn || self.force_render || show_grid != self.grid_shown;
+                    if render && self.opaque {
+                        let frame = self.pixels.as_mut().unwrap().frame_mut();
+                        for alpha in frame.iter_mut().skip(3).step_by(4) {
+                            *alpha = 255;
+                        }
+                    }
+                    if let Some(sink) = self.frame_sink.as_mut() {
+                        sink(self.pixels.as_ref().unwrap().frame(), self.width, self.height);
+                    }
+                    if render {
+                        if let Err(err) = self.pixels.as_ref().unwrap().render() {
+                            self.report_error("pixels.render", err);
+                            event_loop.exit();
+                        } else {
+                            self.force_render = false;
+                            self.grid_shown = show_grid;
+                            self.emit(&FrameworkEvent::Redraw { frame: stats.frame_count });
+                        }
+                    }
+                }
+            }
+            WindowEvent::Moved(_) => {
+                // The window may have moved onto a display with a different refresh rate
+                self.update_target_fps();
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.input.scale_factor = scale_factor;
+                self.force_render = true;
+                // The surface must match the window's new size in physical pixels to stay crisp
+                let size = self.window.as_ref().unwrap().inner_size();
+                if size.width > 0 && size.height > 0 {
+                    if let Err(err) = self.pixels.as_mut().unwrap().resize_surface(size.width, size.height) {
+                        self.report_error("pixels.resize_surface", err);
+                        event_loop.exit()
                     }
-                    self.window.as_ref().unwrap().request_redraw();
                 }
             }
+            WindowEvent::Resized(size) if size.width == 0 || size.height == 0 => {
+                // Minimized on some platforms; a zero-sized surface cannot be rendered to
+                self.set_minimized(event_loop, true);
+            }
+            WindowEvent::Occluded(occluded) => {
+                self.set_minimized(event_loop, occluded);
+            }
             WindowEvent::Resized(size) => {
+                self.set_minimized(event_loop, false);
+                self.force_render = true;
                 if let Err(err) = self
                     .pixels
                     .as_mut()
                     .unwrap()
                     .resize_surface(size.width, size.height)
                 {
-                    log_error("pixels.resize_surface", err);
+                    self.report_error("pixels.resize_surface", err);
                     event_loop.exit()
                 }
             }
             WindowEvent::KeyboardInput { event, .. } => {
+                let mut typed_text = false;
+                if event.state.is_pressed() {
+                    if let Some(text) = &event.text {
+                        let start = self.input.text_input.len();
+                        self.input.text_input.extend(text.chars().filter(|c| !c.is_control()));
+                        typed_text = self.input.text_input.len() > start;
+                    }
+                }
+                // While the world captures text, keys that typed some are not treated as keys
+                let captured = typed_text
+                    && self.world.as_ref().is_some_and(|world| world.captures_text_input());
+
+                if event.state.is_pressed() {
+                    if !event.repeat && !captured {
+                        self.input.logical_keys_pressed.insert(event.logical_key.clone());
+                        self.held_logical_keys.insert(event.physical_key, event.logical_key.clone());
+                    }
+                } else if let Some(key) = self.held_logical_keys.remove(&event.physical_key) {
+                    // Another held key (like the second Shift) may produce the same logical key
+                    if !self.held_logical_keys.values().any(|held| *held == key) {
+                        self.input.logical_keys_pressed.remove(&key);
+                    }
+                }
+
                 if let PhysicalKey::Code(key_code) = event.physical_key {
                     if key_code == KeyCode::Escape {
                         event_loop.exit();
                     }
 
-                    if event.state.is_pressed() {
-                        self.input.keys_pressed.insert(key_code);
-                    } else {
+                    if !event.state.is_pressed() {
                         self.input.keys_pressed.remove(&key_code);
+                    } else if !captured {
+                        if event.repeat {
+                            self.input.keys_repeated.insert(key_code);
+                        } else if self.input.keys_pressed.insert(key_code) {
+                            self.input.keys_just_pressed.insert(key_code);
+                        }
                     }
                 }
             }
             WindowEvent::CursorMoved { position, .. } => {
                 self.input.mouse_position = Some((position.x, position.y));
+                self.input.mouse_buffer_position = self.pixels.as_ref().and_then(|pixels| {
+                    pixels
+                        .window_pos_to_pixel((position.x as f32, position.y as f32))
+                        .ok()
+                        .map(|(x, y)| (x as u32, y as u32))
+                });
             }
             WindowEvent::CursorLeft { .. } => {
                 self.input.mouse_position = None;
+                self.input.mouse_buffer_position = None;
             }
             WindowEvent::MouseInput { state, button, .. } => {
                 let pressed = state.is_pressed();
-                match button {
-                    winit::event::MouseButton::Left => self.input.mouse_buttons.0 = pressed,
-                    winit::event::MouseButton::Middle => self.input.mouse_buttons.1 = pressed,
-                    winit::event::MouseButton::Right => self.input.mouse_buttons.2 = pressed,
-                    _ => {}
+                let (held, just_pressed) = match button {
+                    winit::event::MouseButton::Left => (
+                        &mut self.input.mouse_buttons.0,
+                        &mut self.input.mouse_buttons_just_pressed.0,
+                    ),
+                    winit::event::MouseButton::Middle => (
+                        &mut self.input.mouse_buttons.1,
+                        &mut self.input.mouse_buttons_just_pressed.1,
+                    ),
+                    winit::event::MouseButton::Right => (
+                        &mut self.input.mouse_buttons.2,
+                        &mut self.input.mouse_buttons_just_pressed.2,
+                    ),
+                    _ => return,
+                };
+                if pressed && !*held {
+                    *just_pressed = true;
                 }
+                *held = pressed;
             }
             _ => {}
         }
     }
 }
 
+/// Size and grid settings of the canvas each frame is drawn on
+pub(crate) struct FrameLayout {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) pixel_grid_width: u32,
+    pub(crate) pixel_grid_height: u32,
+    pub(crate) coordinate_system: CoordinateSystem,
+    pub(crate) show_grid: bool,
+    pub(crate) grid_color: Color,
+    pub(crate) grid_style: GridStyle,
+    pub(crate) pixel_aspect: f32,
+}
+
+impl FrameLayout {
+    /// Build a canvas with this layout over `frame`
+    pub(crate) fn canvas<'a>(&self, frame: &'a mut [u8]) -> Canvas<'a> {
+        Canvas::new(
+            frame,
+            self.width,
+            self.height,
+            self.pixel_grid_width,
+            self.pixel_grid_height,
+            self.coordinate_system,
+            self.show_grid,
+            self.grid_color,
+        )
+        .with_pixel_aspect(self.pixel_aspect)
+        .with_grid_style(self.grid_style)
+    }
+}
+
+/// Run the drawing phase of a frame: background, the world's drawing, optional smoothing, widgets
+/// and grid
+///
+/// With `smooth_upscale` the world draws into `scene`, which keeps its unsmoothed picture from
+/// frame to frame, and `frame` gets a smoothed copy of it before the widgets and grid go on top.
+/// Without it an earlier `scene` is copied back into `frame` and dropped, so the world's picture
+/// survives switching smoothing off. Stores the pixel mapping and the ids tagged this frame in
+/// `input`.
+///
+/// Returns whether anything drew into the frame (see `Canvas::was_modified`); the grid lines
+/// don't count, since they are the same every frame
+pub(crate) fn draw_frame<W: World>(
+    world: &mut W,
+    layout: &FrameLayout,
+    frame: &mut [u8],
+    scene: &mut Vec<u8>,
+    smooth_upscale: bool,
+    input: &mut InputState,
+) -> bool {
+    let (mut canvas, scene_drawn) = if smooth_upscale {
+        let fresh = scene.len() != frame.len();
+        if fresh {
+            *scene = frame.to_vec();
+        }
+        let mut scene_canvas = layout.canvas(scene);
+        draw_world(world, &mut scene_canvas);
+        // An untouched scene still has its smoothed copy in the frame from last time
+        let scene_drawn = fresh || scene_canvas.was_modified();
+        if scene_drawn {
+            scene_canvas.smooth_upscale_into(frame);
+        }
+        let ids = scene_canvas.take_ids();
+        let mut canvas = layout.canvas(frame);
+        canvas.restore_ids(ids);
+        (canvas, scene_drawn)
+    } else {
+        let restored = !scene.is_empty();
+        if restored {
+            frame.copy_from_slice(scene);
+            *scene = Vec::new();
+        }
+        let mut canvas = layout.canvas(frame);
+        draw_world(world, &mut canvas);
+        (canvas, restored)
+    };
+    input.pixel_mapping = Some(canvas.pixel_mapping());
+    world.ui(&mut Ui::new(&mut canvas, input));
+    let drawn = scene_drawn || canvas.was_modified();
+    canvas.draw_grid();
+    input.ids = canvas.take_ids();
+    drawn
+}
+
+/// Draw the world's background image and then the world itself
+pub(crate) fn draw_world<W: World>(world: &W, canvas: &mut Canvas) {
+    if let Some(background) = world.background_image() {
+        canvas.draw_background_image(background);
+    }
+    world.draw(canvas);
+}
+
+/// Get the size of the primary monitor in physical pixels
+///
+/// Falls back to the first available monitor on platforms that have no notion of a primary one
+/// (such as Wayland), and returns None when there are no monitors at all
+pub fn primary_monitor_size(event_loop: &ActiveEventLoop) -> Option<(u32, u32)> {
+    let monitor = event_loop
+        .primary_monitor()
+        .or_else(|| event_loop.available_monitors().next())?;
+    let size = monitor.size();
+    Some((size.width, size.height))
+}
+
+fn warn_if_over_budget(phase: &str, time: Duration, budget: Duration) {
+    if time > budget {
+        warn!(
+            "{phase} took {:.2} ms, {:.2} ms over the {:.2} ms frame budget",
+            time.as_secs_f64() * 1000.0,
+            (time - budget).as_secs_f64() * 1000.0,
+            budget.as_secs_f64() * 1000.0,
+        );
+    }
+}
+
 fn log_error<E: std::error::Error + 'static>(method_name: &str, err: E) {
     error!("{method_name}() failed: {err}");
     for source in err.sources().skip(1) {
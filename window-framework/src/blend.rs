@@ -0,0 +1,54 @@
+use crate::canvas::Color;
+
+/// How a source color is combined with the destination pixel beneath it
+///
+/// All modes except `Replace` weight the result by the source alpha, so a fully transparent
+/// source leaves the destination unchanged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// The source overwrites the destination, including its alpha
+    Replace,
+    /// Standard "source over" alpha compositing
+    #[default]
+    Normal,
+    /// Source and destination channels are added, saturating at 255
+    Add,
+    /// Source and destination channels are multiplied, darkening the result
+    Multiply,
+    /// Inverse of multiplying the inverted channels, lightening the result
+    Screen,
+}
+
+impl BlendMode {
+    /// Blend `src` onto `dst`, returning the resulting color
+    ///
+    /// Colors use straight (non-premultiplied) alpha
+    pub fn blend(self, dst: Color, src: Color) -> Color {
+        if self == BlendMode::Replace {
+            return src;
+        }
+
+        let mix = |d: u8, s: u8| -> u8 {
+            match self {
+                BlendMode::Replace | BlendMode::Normal => s,
+                BlendMode::Add => d.saturating_add(s),
+                BlendMode::Multiply => ((d as u16 * s as u16 + 127) / 255) as u8,
+                BlendMode::Screen => 255 - (((255 - d) as u16 * (255 - s) as u16 + 127) / 255) as u8,
+            }
+        };
+
+        let alpha = src.3 as f32 / 255.0;
+        let lerp = |d: u8, s: u8| -> u8 {
+            let target = mix(d, s) as f32;
+            (d as f32 + (target - d as f32) * alpha).round() as u8
+        };
+        let out_alpha = src.3 as f32 + dst.3 as f32 * (1.0 - alpha);
+
+        (
+            lerp(dst.0, src.0),
+            lerp(dst.1, src.1),
+            lerp(dst.2, src.2),
+            out_alpha.round() as u8,
+        )
+    }
+}
@@ -1,21 +1,240 @@
+use std::collections::HashMap;
+
+use crate::blend::BlendMode;
+use crate::colormap::Colormap;
+use crate::font::{self, GLYPH_ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH, LINE_HEIGHT};
+use crate::graph::{EdgeStyle, Graph};
+use crate::lut::ColorLut;
+use crate::owned_canvas::OwnedCanvas;
+use crate::sprite::{Sprite, SpriteRegion};
+use crate::stroke::{stroke_pieces, LineCap, LineJoin, Piece};
+
+/// An RGBA color where each component is 0-255
+pub type Color = (u8, u8, u8, u8);
+
 /// Coordinate system for the canvas
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum CoordinateSystem {
     /// Origin (0, 0) is at the top-left corner
+    #[default]
     TopLeft,
     /// Origin (0, 0) is at the center of the canvas
     Center,
 }
 
-impl Default for CoordinateSystem {
-    fn default() -> Self {
-        CoordinateSystem::TopLeft
+/// An axis-aligned rectangle in user coordinates
+///
+/// Covers `x..x + width` and `y..y + height`, the same region `Canvas::fill_rect` fills
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Create a new rectangle
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Whether the point (x, y) lies inside the rectangle
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && ((x - self.x) as u32) < self.width
+            && ((y - self.y) as u32) < self.height
+    }
+}
+
+/// Rotation of text in exact quarter turns, clockwise as seen on screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// Upright, reading left to right
+    #[default]
+    None,
+    /// Reading top to bottom
+    Cw90,
+    /// Upside down, reading right to left
+    Cw180,
+    /// Reading bottom to top, as on a y axis label
+    Cw270,
+}
+
+/// How the radius of a spiral drawn by `Canvas::draw_spiral` grows with its angle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpiralKind {
+    /// Radius grows linearly, so successive turns are evenly spaced
+    #[default]
+    Archimedean,
+    /// Radius grows exponentially from 1 pixel, so turns spread out further from the center
+    Logarithmic,
+}
+
+/// Which way a one-dimensional element such as a legend runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    /// Left to right
+    Horizontal,
+    /// Bottom to top
+    #[default]
+    Vertical,
+}
+
+/// How the grid between logical pixels is drawn when `show_grid` is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridStyle {
+    /// Full 1-pixel lines along every edge between logical pixels
+    #[default]
+    Lines,
+    /// A single physical pixel at every corner where the lines would cross
+    Dots,
+}
+
+/// A color-vision deficiency simulated by `Canvas::simulate_cvd`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvdKind {
+    /// No working long-wavelength (red) cones
+    Protanopia,
+    /// No working medium-wavelength (green) cones
+    Deuteranopia,
+    /// No working short-wavelength (blue) cones
+    Tritanopia,
+}
+
+impl CvdKind {
+    /// Linear RGB transform simulating the deficiency at full severity (Machado et al. 2009)
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            CvdKind::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            CvdKind::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            CvdKind::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// A single channel of an RGBA color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+impl Channel {
+    /// Index of the channel within an RGBA pixel
+    pub fn index(self) -> usize {
+        match self {
+            Channel::Red => 0,
+            Channel::Green => 1,
+            Channel::Blue => 2,
+            Channel::Alpha => 3,
+        }
+    }
+}
+
+/// How physical buffer pixels map to logical pixels and user coordinates
+///
+/// Captured from a `Canvas` with `Canvas::pixel_mapping`, so code without a canvas (such as
+/// `World::handle_input`) can still convert buffer positions to user coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelMapping {
+    pub pixel_scale_x: u32,
+    pub pixel_scale_y: u32,
+    pub logical_width: u32,
+    pub logical_height: u32,
+    pub coordinate_system: CoordinateSystem,
+}
+
+impl PixelMapping {
+    /// Convert a physical buffer position to the user coordinates of the logical pixel covering it
+    ///
+    /// Returns None if the position lies outside the logical grid
+    pub fn physical_to_user(&self, phys_x: u32, phys_y: u32) -> Option<(i32, i32)> {
+        let (logical_x, logical_y) = self.physical_to_logical(phys_x, phys_y)?;
+        Some(self.logical_to_user(logical_x, logical_y))
+    }
+
+    /// Convert a physical buffer position to the logical buffer coordinates covering it
+    pub(crate) fn physical_to_logical(&self, phys_x: u32, phys_y: u32) -> Option<(u32, u32)> {
+        let logical_x = phys_x.checked_div(self.pixel_scale_x)?;
+        let logical_y = phys_y.checked_div(self.pixel_scale_y)?;
+        (logical_x < self.logical_width && logical_y < self.logical_height)
+            .then_some((logical_x, logical_y))
+    }
+
+    /// Convert logical buffer coordinates to user coordinates
+    fn logical_to_user(&self, logical_x: u32, logical_y: u32) -> (i32, i32) {
+        match self.coordinate_system {
+            CoordinateSystem::TopLeft => (logical_x as i32, logical_y as i32),
+            CoordinateSystem::Center => {
+                let center_x = (self.logical_width / 2) as i32;
+                let center_y = (self.logical_height / 2) as i32;
+                (logical_x as i32 - center_x, center_y - logical_y as i32)
+            }
+        }
+    }
+}
+
+/// A rectangular region of a canvas with its own coordinate system and resolution
+///
+/// The region is given in the parent canvas's logical pixels, measured from its top-left corner
+/// regardless of the parent's coordinate system. Inside the viewport, drawing is clipped to the
+/// region and coordinates are interpreted in the viewport's own coordinate system, with (0, 0)
+/// at the region's top-left corner or center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub coordinate_system: CoordinateSystem,
+    /// Number of parent logical pixels (per axis) covered by one viewport logical pixel
+    pub scale: u32,
+}
+
+impl Viewport {
+    /// Create a viewport covering the given region of the parent canvas with a scale of 1
+    pub fn new(x: u32, y: u32, width: u32, height: u32, coordinate_system: CoordinateSystem) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            coordinate_system,
+            scale: 1,
+        }
+    }
+
+    /// Make each viewport logical pixel cover `scale`×`scale` parent logical pixels
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale.max(1);
+        self
     }
 }
 
 /// A canvas for drawing pixels with configurable coordinate systems
 pub struct Canvas<'a> {
     frame: &'a mut [u8],
+    /// Width of a row of the underlying frame buffer, in physical pixels
+    stride: u32,
+    /// Physical position of this canvas's top-left pixel within the frame buffer
+    origin_x: u32,
+    origin_y: u32,
     physical_width: u32,
     physical_height: u32,
     logical_width: u32,
@@ -24,11 +243,22 @@ pub struct Canvas<'a> {
     pixel_scale_y: u32,
     coordinate_system: CoordinateSystem,
     show_grid: bool,
-    grid_color: (u8, u8, u8, u8),
+    grid_color: Color,
+    grid_style: GridStyle,
+    viewports: HashMap<String, Viewport>,
+    /// Whether any pixel has been written since the canvas was created
+    modified: bool,
+    /// Number of writes to each logical pixel, in buffer order
+    #[cfg(feature = "overdraw")]
+    overdraw: Vec<u16>,
+    /// Object id tagged on each logical pixel in buffer order, allocated by the first
+    /// `set_pixel_id` (empty until then)
+    ids: Vec<u32>,
 }
 
 impl<'a> Canvas<'a> {
     /// Create a new canvas wrapping a frame buffer
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         frame: &'a mut [u8],
         physical_width: u32,
@@ -37,13 +267,16 @@ impl<'a> Canvas<'a> {
         logical_height: u32,
         coordinate_system: CoordinateSystem,
         show_grid: bool,
-        grid_color: (u8, u8, u8, u8),
+        grid_color: Color,
     ) -> Self {
-        let pixel_scale_x = physical_width / logical_width;
-        let pixel_scale_y = physical_height / logical_height;
+        let (pixel_scale_x, pixel_scale_y) =
+            pixel_scales(physical_width, physical_height, logical_width, logical_height, 1.0);
 
         Self {
             frame,
+            stride: physical_width,
+            origin_x: 0,
+            origin_y: 0,
             physical_width,
             physical_height,
             logical_width,
@@ -53,9 +286,38 @@ impl<'a> Canvas<'a> {
             coordinate_system,
             show_grid,
             grid_color,
+            grid_style: GridStyle::Lines,
+            viewports: HashMap::new(),
+            modified: false,
+            #[cfg(feature = "overdraw")]
+            overdraw: vec![0; (logical_width * logical_height) as usize],
+            ids: Vec::new(),
         }
     }
 
+    /// Draw the grid in the given style instead of as full lines
+    pub fn with_grid_style(mut self, grid_style: GridStyle) -> Self {
+        self.grid_style = grid_style;
+        self
+    }
+
+    /// Stretch logical pixels to the given width:height aspect ratio
+    ///
+    /// With an aspect of 1.0 (the default) the horizontal and vertical scales are each derived
+    /// independently from the physical and logical sizes. Any other aspect picks the largest
+    /// block of that shape that still fits the logical grid into the physical buffer, so part of
+    /// the buffer may stay unused; see `pixel_scales`
+    pub fn with_pixel_aspect(mut self, pixel_aspect: f32) -> Self {
+        (self.pixel_scale_x, self.pixel_scale_y) = pixel_scales(
+            self.physical_width,
+            self.physical_height,
+            self.logical_width,
+            self.logical_height,
+            pixel_aspect,
+        );
+        self
+    }
+
     /// Get the logical width of the canvas (in logical pixels)
     pub fn width(&self) -> u32 {
         self.logical_width
@@ -66,21 +328,123 @@ impl<'a> Canvas<'a> {
         self.logical_height
     }
 
+    /// Size of the physical RGBA pixels this canvas covers, in bytes
+    ///
+    /// This is `physical_width * physical_height * 4`; for a sub-canvas it counts only its own
+    /// region, which is borrowed from the parent's buffer rather than allocated separately
+    pub fn buffer_size_bytes(&self) -> usize {
+        self.physical_width as usize * self.physical_height as usize * 4
+    }
+
     /// Get the coordinate system of the canvas
     pub fn coordinate_system(&self) -> CoordinateSystem {
         self.coordinate_system
     }
 
+    /// Get the mapping from physical pixels to user coordinates used by this canvas
+    pub fn pixel_mapping(&self) -> PixelMapping {
+        PixelMapping {
+            pixel_scale_x: self.pixel_scale_x,
+            pixel_scale_y: self.pixel_scale_y,
+            logical_width: self.logical_width,
+            logical_height: self.logical_height,
+            coordinate_system: self.coordinate_system,
+        }
+    }
+
+    /// Whether anything has drawn into this canvas since it was created
+    ///
+    /// Set by every drawing call, even one that lands entirely off the canvas or writes the colors
+    /// that were already there. Drawing through `in_viewport` or `in_inset` counts too. `App`
+    /// builds a fresh canvas every frame and skips uploading and presenting frames that nothing
+    /// drew into, so a world that leaves a still picture alone costs next to no GPU time
+    pub fn was_modified(&self) -> bool {
+        self.modified
+    }
+
+    /// How many times each logical pixel has been written since the canvas was created
+    ///
+    /// One count per logical pixel in buffer order (top row first, left to right), incremented
+    /// by every per-pixel write such as `set_pixel`, `blend_pixel` and the shapes built on them.
+    /// Whole-buffer operations like `clear` and the filters are not counted, and neither is
+    /// drawing through `in_viewport` or `in_inset`, which goes to its own canvas. `App` builds a
+    /// fresh canvas every frame, so the counts cover the current frame's `draw` so far
+    #[cfg(feature = "overdraw")]
+    pub fn overdraw_map(&self) -> &[u16] {
+        &self.overdraw
+    }
+
+    /// Set a pixel like `set_pixel` and tag it with the id of the object it belongs to
+    ///
+    /// The id buffer runs parallel to the pixels at logical resolution, so `id_at` (or
+    /// `InputState::picked_id` after the frame) can later tell which object is visible at a
+    /// point regardless of its colors. Any other drawing over a tagged pixel, `clear` included,
+    /// untags it, so an id always belongs to what was drawn there last; filters that only adjust
+    /// colors, like `threshold`, keep the ids. `u32::MAX` is reserved for untagged pixels and
+    /// is not stored. The buffer is only allocated once an id is first written, and starts out
+    /// empty on every canvas, so with `App` building a fresh canvas each frame the ids always
+    /// describe the current frame. Ids written through `in_viewport` or `in_inset` go to that
+    /// region's own canvas and are not kept.
+    ///
+    /// Returns true if the pixel was set, false if out of bounds
+    pub fn set_pixel_id(&mut self, x: i32, y: i32, color: Color, id: u32) -> bool {
+        self.modified = true;
+        let Some((logical_x, logical_y)) = self.to_logical_coords(x, y) else {
+            return false;
+        };
+        self.set_logical_pixel(logical_x, logical_y, color);
+        if id != NO_ID {
+            if self.ids.is_empty() {
+                self.ids = vec![NO_ID; (self.logical_width * self.logical_height) as usize];
+            }
+            self.ids[(logical_y * self.logical_width + logical_x) as usize] = id;
+        }
+        true
+    }
+
+    /// Get the object id that `set_pixel_id` last tagged the pixel at (x, y) with
+    ///
+    /// Returns None for untagged pixels and coordinates outside the canvas
+    pub fn id_at(&self, x: i32, y: i32) -> Option<u32> {
+        let (logical_x, logical_y) = self.to_logical_coords(x, y)?;
+        self.ids
+            .get((logical_y * self.logical_width + logical_x) as usize)
+            .copied()
+            .filter(|&id| id != NO_ID)
+    }
+
+    /// Move the id buffer out of the canvas, leaving it untagged
+    pub(crate) fn take_ids(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.ids)
+    }
+
+    /// Hand back an id buffer taken from a canvas with the same logical size
+    pub(crate) fn restore_ids(&mut self, ids: Vec<u32>) {
+        self.ids = ids;
+    }
+
+    /// Replace the canvas with a heatmap of `overdraw_map`, to spot pixels painted repeatedly
+    ///
+    /// Each logical pixel is colored `colormap.sample(count / max_count)`, so untouched pixels
+    /// take the map's start and `max_count` writes or more its end. Call it at the end of
+    /// `draw`; painting the heatmap itself is not counted
+    #[cfg(feature = "overdraw")]
+    pub fn draw_overdraw_heatmap(&mut self, colormap: &Colormap, max_count: u16) {
+        self.modified = true;
+        let counts = std::mem::take(&mut self.overdraw);
+        for logical_y in 0..self.logical_height {
+            for logical_x in 0..self.logical_width {
+                let count = counts[(logical_y * self.logical_width + logical_x) as usize];
+                let color = colormap.sample(count as f32 / max_count.max(1) as f32);
+                self.set_logical_pixel(logical_x, logical_y, color);
+            }
+        }
+        self.overdraw = counts;
+    }
+
     /// Convert user coordinates to logical buffer coordinates
     fn to_logical_coords(&self, x: i32, y: i32) -> Option<(u32, u32)> {
-        let (logical_x, logical_y) = match self.coordinate_system {
-            CoordinateSystem::TopLeft => (x, y),
-            CoordinateSystem::Center => {
-                let center_x = (self.logical_width / 2) as i32;
-                let center_y = (self.logical_height / 2) as i32;
-                (x + center_x, center_y - y)
-            }
-        };
+        let (logical_x, logical_y) = self.to_unclipped_logical_coords(x, y);
 
         // Check bounds
         if logical_x >= 0 && logical_x < self.logical_width as i32
@@ -91,10 +455,67 @@ impl<'a> Canvas<'a> {
         }
     }
 
+    /// Convert user coordinates to logical buffer coordinates without bounds checking
+    fn to_unclipped_logical_coords(&self, x: i32, y: i32) -> (i32, i32) {
+        match self.coordinate_system {
+            CoordinateSystem::TopLeft => (x, y),
+            CoordinateSystem::Center => {
+                let center_x = (self.logical_width / 2) as i32;
+                let center_y = (self.logical_height / 2) as i32;
+                // Saturating, which keeps far-off coordinates off the canvas instead of wrapping
+                (x.saturating_add(center_x), center_y.saturating_sub(y))
+            }
+        }
+    }
+
+    /// Convert logical buffer coordinates back to user coordinates
+    fn to_user_coords(&self, logical_x: u32, logical_y: u32) -> (i32, i32) {
+        self.pixel_mapping().logical_to_user(logical_x, logical_y)
+    }
+
+    /// The smallest and largest user x and y on the canvas, as (min_x, min_y, max_x, max_y)
+    ///
+    /// Returns None for a canvas without logical pixels
+    fn user_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        if self.logical_width == 0 || self.logical_height == 0 {
+            return None;
+        }
+        let (corner_x, corner_y) = self.to_user_coords(0, 0);
+        let (far_x, far_y) = self.to_user_coords(self.logical_width - 1, self.logical_height - 1);
+        Some((corner_x.min(far_x), corner_y.min(far_y), corner_x.max(far_x), corner_y.max(far_y)))
+    }
+
+    /// Byte offset of a physical pixel of this canvas within the frame buffer
+    fn pixel_index(&self, phys_x: u32, phys_y: u32) -> usize {
+        (((self.origin_y + phys_y) * self.stride + self.origin_x + phys_x) * 4) as usize
+    }
+
+    /// Iterate over the RGBA bytes of every physical pixel of this canvas, row by row
+    fn physical_chunks(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        let (start, end) = (self.origin_x as usize * 4, (self.origin_x + self.physical_width) as usize * 4);
+        self.frame
+            .chunks_exact(self.stride as usize * 4)
+            .skip(self.origin_y as usize)
+            .take(self.physical_height as usize)
+            .flat_map(move |row| row[start..end].chunks_exact(4))
+    }
+
+    /// Iterate mutably over the RGBA bytes of every physical pixel of this canvas, row by row
+    fn physical_chunks_mut(&mut self) -> impl Iterator<Item = &mut [u8]> + '_ {
+        self.modified = true;
+        let (start, end) = (self.origin_x as usize * 4, (self.origin_x + self.physical_width) as usize * 4);
+        self.frame
+            .chunks_exact_mut(self.stride as usize * 4)
+            .skip(self.origin_y as usize)
+            .take(self.physical_height as usize)
+            .flat_map(move |row| row[start..end].chunks_exact_mut(4))
+    }
+
     /// Set a physical pixel in the frame buffer
-    fn set_physical_pixel(&mut self, phys_x: u32, phys_y: u32, color: (u8, u8, u8, u8)) {
+    fn set_physical_pixel(&mut self, phys_x: u32, phys_y: u32, color: Color) {
         if phys_x < self.physical_width && phys_y < self.physical_height {
-            let idx = ((phys_y * self.physical_width + phys_x) * 4) as usize;
+            self.modified = true;
+            let idx = self.pixel_index(phys_x, phys_y);
             self.frame[idx] = color.0;
             self.frame[idx + 1] = color.1;
             self.frame[idx + 2] = color.2;
@@ -103,9 +524,9 @@ impl<'a> Canvas<'a> {
     }
 
     /// Get a physical pixel from the frame buffer
-    fn get_physical_pixel(&self, phys_x: u32, phys_y: u32) -> Option<(u8, u8, u8, u8)> {
+    fn get_physical_pixel(&self, phys_x: u32, phys_y: u32) -> Option<Color> {
         if phys_x < self.physical_width && phys_y < self.physical_height {
-            let idx = ((phys_y * self.physical_width + phys_x) * 4) as usize;
+            let idx = self.pixel_index(phys_x, phys_y);
             Some((
                 self.frame[idx],
                 self.frame[idx + 1],
@@ -117,6 +538,37 @@ impl<'a> Canvas<'a> {
         }
     }
 
+    /// Fill the block of physical pixels covered by a logical pixel (in buffer coordinates)
+    fn set_logical_pixel(&mut self, logical_x: u32, logical_y: u32, color: Color) {
+        #[cfg(feature = "overdraw")]
+        if let Some(count) = self
+            .overdraw
+            .get_mut((logical_y * self.logical_width + logical_x) as usize)
+        {
+            *count = count.saturating_add(1);
+        }
+        // Whatever was tagged here is now covered
+        if let Some(id) = self.ids.get_mut((logical_y * self.logical_width + logical_x) as usize) {
+            *id = NO_ID;
+        }
+
+        // Calculate the top-left physical pixel for this logical pixel
+        let phys_x_start = logical_x * self.pixel_scale_x;
+        let phys_y_start = logical_y * self.pixel_scale_y;
+
+        // Fill the block of physical pixels
+        for dy in 0..self.pixel_scale_y {
+            for dx in 0..self.pixel_scale_x {
+                self.set_physical_pixel(phys_x_start + dx, phys_y_start + dy, color);
+            }
+        }
+    }
+
+    /// Get the color of a logical pixel (in buffer coordinates) from its top-left physical pixel
+    fn get_logical_pixel(&self, logical_x: u32, logical_y: u32) -> Option<Color> {
+        self.get_physical_pixel(logical_x * self.pixel_scale_x, logical_y * self.pixel_scale_y)
+    }
+
     /// Set a logical pixel at the given coordinates with the specified color
     ///
     /// This will fill the corresponding block of physical pixels
@@ -124,42 +576,183 @@ impl<'a> Canvas<'a> {
     /// Color format: (R, G, B, A) where each component is 0-255
     ///
     /// Returns true if the pixel was set, false if out of bounds
-    pub fn set_pixel(&mut self, x: i32, y: i32, color: (u8, u8, u8, u8)) -> bool {
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) -> bool {
+        self.modified = true;
         if let Some((logical_x, logical_y)) = self.to_logical_coords(x, y) {
-            // Calculate the top-left physical pixel for this logical pixel
-            let phys_x_start = logical_x * self.pixel_scale_x;
-            let phys_y_start = logical_y * self.pixel_scale_y;
-
-            // Fill the block of physical pixels
-            for dy in 0..self.pixel_scale_y {
-                for dx in 0..self.pixel_scale_x {
-                    self.set_physical_pixel(phys_x_start + dx, phys_y_start + dy, color);
-                }
-            }
+            self.set_logical_pixel(logical_x, logical_y, color);
             true
         } else {
             false
         }
     }
 
+    /// Set the logical pixel containing the point (x, y), for positions computed as floats
+    ///
+    /// Coordinates are floored, so pixel (i, j) owns every point from i to just below i + 1 and
+    /// from j to just below j + 1: 2.5 and 2.99 both land on 2, while -0.5 lands on -1. Unlike
+    /// rounding this has no special case at .5, so a point moving steadily crosses each pixel
+    /// boundary exactly once, at the integer. Nothing is drawn for NaN or infinite coordinates.
+    ///
+    /// Returns true if the pixel was set, false if out of bounds
+    pub fn set_pixel_f(&mut self, x: f32, y: f32, color: Color) -> bool {
+        self.modified = true;
+        if !x.is_finite() || !y.is_finite() {
+            return false;
+        }
+        self.set_pixel(x.floor() as i32, y.floor() as i32, color)
+    }
+
     /// Get the color of a logical pixel at the given coordinates
     ///
     /// Returns the color of the top-left physical pixel in the logical pixel block
     ///
     /// Returns None if the coordinates are out of bounds
-    pub fn get_pixel(&self, x: i32, y: i32) -> Option<(u8, u8, u8, u8)> {
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        let (logical_x, logical_y) = self.to_logical_coords(x, y)?;
+        self.get_logical_pixel(logical_x, logical_y)
+    }
+
+    /// Get the block of physical pixels that the logical pixel at (x, y) fills
+    ///
+    /// Returns (px, py, width, height), with (px, py) the block's top-left physical pixel
+    /// counted from the top-left corner of this canvas, as a diagnostic for the coordinate system
+    /// and pixel scale. The block is clipped to the physical buffer. Returns None if (x, y) is
+    /// outside the grid
+    pub fn physical_block(&self, x: i32, y: i32) -> Option<(u32, u32, u32, u32)> {
+        let (logical_x, logical_y) = self.to_logical_coords(x, y)?;
+        let (px, py) = (logical_x * self.pixel_scale_x, logical_y * self.pixel_scale_y);
+        let width = self.pixel_scale_x.min(self.physical_width.saturating_sub(px));
+        let height = self.pixel_scale_y.min(self.physical_height.saturating_sub(py));
+        Some((px, py, width, height))
+    }
+
+    /// Call `f` with the user coordinates and color of every logical pixel
+    ///
+    /// Pixels are visited in row-major order of the buffer (top row first, left to right),
+    /// and the coordinates passed to `f` are in the canvas's coordinate system
+    pub fn for_each_pixel(&self, mut f: impl FnMut(i32, i32, Color)) {
+        for logical_y in 0..self.logical_height {
+            for logical_x in 0..self.logical_width {
+                if let Some(color) = self.get_logical_pixel(logical_x, logical_y) {
+                    let (x, y) = self.to_user_coords(logical_x, logical_y);
+                    f(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Replace every logical pixel with the color returned by `f`
+    ///
+    /// `f` receives the user coordinates and current color of each pixel, in the same order as
+    /// `for_each_pixel`
+    pub fn map_pixels(&mut self, mut f: impl FnMut(i32, i32, Color) -> Color) {
+        self.modified = true;
+        for logical_y in 0..self.logical_height {
+            for logical_x in 0..self.logical_width {
+                if let Some(color) = self.get_logical_pixel(logical_x, logical_y) {
+                    let (x, y) = self.to_user_coords(logical_x, logical_y);
+                    self.set_logical_pixel(logical_x, logical_y, f(x, y, color));
+                }
+            }
+        }
+    }
+
+    /// Alpha-composite a color over the logical pixel at the given coordinates
+    ///
+    /// Uses `BlendMode::Normal`, so an alpha of 255 behaves like `set_pixel` and an alpha of 0
+    /// leaves the pixel unchanged
+    ///
+    /// Returns true if the pixel was blended, false if out of bounds
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Color) -> bool {
+        self.modified = true;
         if let Some((logical_x, logical_y)) = self.to_logical_coords(x, y) {
-            let phys_x = logical_x * self.pixel_scale_x;
-            let phys_y = logical_y * self.pixel_scale_y;
-            self.get_physical_pixel(phys_x, phys_y)
+            self.blend_logical_pixel(logical_x, logical_y, color, BlendMode::Normal);
+            true
         } else {
-            None
+            false
+        }
+    }
+
+    /// Blend a color onto a logical pixel (in buffer coordinates) with the given mode
+    fn blend_logical_pixel(&mut self, logical_x: u32, logical_y: u32, color: Color, mode: BlendMode) {
+        if let Some(dst) = self.get_logical_pixel(logical_x, logical_y) {
+            self.set_logical_pixel(logical_x, logical_y, mode.blend(dst, color));
+        }
+    }
+
+    /// Composite an off-screen canvas onto this one
+    ///
+    /// The top-left pixel of `src` lands on the logical pixel at (x, y), and the rest of `src`
+    /// extends right and down in buffer order regardless of the coordinate system. Each source
+    /// pixel's alpha is multiplied by `opacity` (clamped to 0.0-1.0) before blending with `mode`.
+    ///
+    /// Source pixels falling outside this canvas are clipped
+    pub fn composite(&mut self, x: i32, y: i32, src: &OwnedCanvas, mode: BlendMode, opacity: f32) {
+        self.modified = true;
+        let opacity = opacity.clamp(0.0, 1.0);
+        let (origin_x, origin_y) = self.to_unclipped_logical_coords(x, y);
+
+        for src_y in 0..src.height() {
+            let dst_y = origin_y + src_y as i32;
+            if dst_y < 0 || dst_y >= self.logical_height as i32 {
+                continue;
+            }
+            for src_x in 0..src.width() {
+                let dst_x = origin_x + src_x as i32;
+                if dst_x < 0 || dst_x >= self.logical_width as i32 {
+                    continue;
+                }
+                if let Some(color) = src.get_pixel(src_x, src_y) {
+                    let alpha = (color.3 as f32 * opacity).round() as u8;
+                    let color = (color.0, color.1, color.2, alpha);
+                    self.blend_logical_pixel(dst_x as u32, dst_y as u32, color, mode);
+                }
+            }
+        }
+    }
+
+    /// Composite an off-screen canvas onto this one through a soft mask of the same size
+    ///
+    /// `src` is placed and clipped as by `composite`, and each of its pixels is alpha-blended
+    /// with its alpha scaled by the mask pixel at the same position: the mask's luminance times
+    /// its alpha, so opaque white shows the source fully and black or transparent hides it.
+    /// Gray levels in between give partial reveals, as for wipe transitions.
+    ///
+    /// Returns false without drawing anything if `mask` and `src` differ in size
+    pub fn composite_masked(&mut self, x: i32, y: i32, src: &OwnedCanvas, mask: &OwnedCanvas) -> bool {
+        self.modified = true;
+        if (src.width(), src.height()) != (mask.width(), mask.height()) {
+            return false;
+        }
+        let (origin_x, origin_y) = self.to_unclipped_logical_coords(x, y);
+
+        for src_y in 0..src.height() {
+            let dst_y = origin_y + src_y as i32;
+            if dst_y < 0 || dst_y >= self.logical_height as i32 {
+                continue;
+            }
+            for src_x in 0..src.width() {
+                let dst_x = origin_x + src_x as i32;
+                if dst_x < 0 || dst_x >= self.logical_width as i32 {
+                    continue;
+                }
+                let pixels = (src.get_pixel(src_x, src_y), mask.get_pixel(src_x, src_y));
+                if let (Some(color), Some(coverage)) = pixels {
+                    let weight = luminance(coverage) as u32 * coverage.3 as u32;
+                    let alpha = ((color.3 as u32 * weight + 255 * 255 / 2) / (255 * 255)) as u8;
+                    let color = (color.0, color.1, color.2, alpha);
+                    self.blend_logical_pixel(dst_x as u32, dst_y as u32, color, BlendMode::Normal);
+                }
+            }
         }
+        true
     }
 
     /// Clear the entire canvas with the specified color
-    pub fn clear(&mut self, color: (u8, u8, u8, u8)) {
-        for chunk in self.frame.chunks_exact_mut(4) {
+    pub fn clear(&mut self, color: Color) {
+        self.modified = true;
+        self.ids.clear();
+        for chunk in self.physical_chunks_mut() {
             chunk[0] = color.0;
             chunk[1] = color.1;
             chunk[2] = color.2;
@@ -167,11 +760,29 @@ impl<'a> Canvas<'a> {
         }
     }
 
+    /// Blend every physical pixel towards `color` by `amount`, for a full-screen flash
+    ///
+    /// `amount` is clamped to 0.0-1.0: 0 leaves the canvas unchanged and 1 is the same as
+    /// `clear(color)`, with all four channels interpolated linearly in between. Call it after the
+    /// rest of the scene with an amount that decays over a few frames, e.g. from a `Timeline`
+    pub fn flash(&mut self, color: Color, amount: f32) {
+        self.modified = true;
+        let amount = amount.clamp(0.0, 1.0);
+        if amount == 0.0 {
+            return;
+        }
+        for chunk in self.physical_chunks_mut() {
+            let flashed = lerp_color((chunk[0], chunk[1], chunk[2], chunk[3]), color, amount);
+            chunk.copy_from_slice(&[flashed.0, flashed.1, flashed.2, flashed.3]);
+        }
+    }
+
     /// Fill a rectangular region with the specified color
     ///
     /// The rectangle is defined by (x, y) as the top-left corner and (width, height) as dimensions
     /// when using TopLeft coordinates, or centered at (x, y) when using Center coordinates
-    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: (u8, u8, u8, u8)) {
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        self.modified = true;
         for dy in 0..height as i32 {
             for dx in 0..width as i32 {
                 self.set_pixel(x + dx, y + dy, color);
@@ -179,34 +790,3327 @@ impl<'a> Canvas<'a> {
         }
     }
 
-    /// Draw grid lines between logical pixels (internal method, called automatically if show_grid is true)
-    pub(crate) fn draw_grid(&mut self) {
-        if !self.show_grid || self.pixel_scale_x <= 1 || self.pixel_scale_y <= 1 {
+    /// Fill a rectangle given in float coordinates, snapped to the pixel grid
+    ///
+    /// Both edges along each axis are floored as in `set_pixel_f`, so the region covers pixels
+    /// from `floor(x)` up to but excluding `floor(x + width)`, and likewise for y. A rectangle
+    /// with a whole-number size therefore always covers the same number of pixels however it
+    /// is positioned, instead of growing and shrinking as its edges round differently. Negative
+    /// sizes and non-finite values draw nothing
+    pub fn fill_rect_f(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        self.modified = true;
+        if ![x, y, width, height].iter().all(|value| value.is_finite()) {
+            return;
+        }
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
             return;
+        };
+        // Clamp the edges to the canvas (the far ones are exclusive) while still in floats, so
+        // huge rectangles neither saturate the conversion nor loop over off-canvas pixels
+        let edge = |value: f32, min: i32, max: i32| value.floor().clamp(min as f32, max as f32 + 1.0) as i32;
+        let (x0, x1) = (edge(x, min_x, max_x), edge(x + width, min_x, max_x));
+        let (y0, y1) = (edge(y, min_y, max_y), edge(y + height, min_y, max_y));
+        if x1 > x0 && y1 > y0 {
+            self.fill_rect(x0, y0, (x1 - x0) as u32, (y1 - y0) as u32, color);
         }
+    }
 
-        // Draw vertical lines (including boundaries)
-        for logical_x in 0..self.logical_width {
-            let phys_x = logical_x * self.pixel_scale_x;
-            for phys_y in 0..self.physical_height {
-                self.set_physical_pixel(phys_x, phys_y, self.grid_color);
+    /// Fill a rectangular region through a repeating 8×8 stipple pattern
+    ///
+    /// Covers the same pixels as `fill_rect`, but only paints those whose bit in `pattern` is
+    /// set; the others are left unchanged. Each byte of `pattern` is one row, most significant
+    /// byte first, and within a row the most significant bit is the leftmost column. The pattern
+    /// is tiled from the user coordinate origin rather than from (x, y), so the pixel at (x, y)
+    /// uses row `y mod 8` and column `x mod 8` and adjacent fills line up seamlessly. A pattern
+    /// of all ones is the same as `fill_rect`, and 0 draws nothing; for example
+    /// `0xaa55_aa55_aa55_aa55` gives a checkerboard
+    pub fn fill_rect_stipple(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        pattern: u64,
+        color: Color,
+    ) {
+        self.modified = true;
+        if pattern == 0 {
+            return;
+        }
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+        // In i64 so the far edges can't overflow, and only the part on the canvas is visited;
+        // the phase comes from the actual coordinates, so clipping doesn't shift the pattern
+        let (x, y) = (x as i64, y as i64);
+        for py in y.max(min_y as i64)..(y + height as i64).min(max_y as i64 + 1) {
+            let row = (pattern >> (56 - 8 * py.rem_euclid(8))) as u8;
+            for px in x.max(min_x as i64)..(x + width as i64).min(max_x as i64 + 1) {
+                if row & (0x80 >> px.rem_euclid(8)) != 0 {
+                    self.set_pixel(px as i32, py as i32, color);
+                }
             }
         }
-        // Draw right edge
-        for phys_y in 0..self.physical_height {
-            self.set_physical_pixel(self.physical_width - 1, phys_y, self.grid_color);
+    }
+
+    /// Alpha-composite a color over a rectangular region, covering the same pixels as `fill_rect`
+    ///
+    /// Each logical pixel is blended as by `blend_pixel`, so an opaque color matches `fill_rect`
+    /// and an alpha of 128 gives a 50% overlay, as for dimming panels and tooltips
+    pub fn fill_rect_blended(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        self.modified = true;
+        for dy in 0..height as i32 {
+            for dx in 0..width as i32 {
+                self.blend_pixel(x + dx, y + dy, color);
+            }
         }
+    }
 
-        // Draw horizontal lines (including boundaries)
-        for logical_y in 0..self.logical_height {
-            let phys_y = logical_y * self.pixel_scale_y;
-            for phys_x in 0..self.physical_width {
-                self.set_physical_pixel(phys_x, phys_y, self.grid_color);
+    /// Draw the 1-pixel outline of the rectangle `fill_rect` would fill, as a dashed line
+    ///
+    /// The pattern of `dash_len` drawn pixels followed by `gap_len` skipped ones runs
+    /// continuously around the outline, starting at (x, y) and going along the x axis first.
+    /// `phase` shifts the dashes forward along the outline, so incrementing it every frame gives
+    /// the "marching ants" of a selection marquee. A `gap_len` of 0 draws a solid outline and a
+    /// `dash_len` of 0 draws nothing
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rect_dashed(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: Color,
+        dash_len: u32,
+        gap_len: u32,
+        phase: u32,
+    ) {
+        self.modified = true;
+        for (px, py) in dashed_outline(x, y, width, height, dash_len, gap_len, phase) {
+            self.set_pixel(px, py, color);
+        }
+    }
+
+    /// Draw a dashed guide rectangle `inset` logical pixels in from every edge of the canvas
+    ///
+    /// The outline runs along the outermost pixels still inside the safe area, with the dashes
+    /// `draw_rect_dashed` draws for `SAFE_AREA_DASH` pixels on and off. Its pixels are blended
+    /// as by `blend_pixel`, so a translucent color keeps the content underneath readable.
+    /// Nothing is drawn when the inset leaves no room
+    pub fn draw_safe_area(&mut self, inset: u32, color: Color) {
+        self.modified = true;
+        let margin = inset.saturating_mul(2);
+        if margin >= self.logical_width || margin >= self.logical_height {
+            return;
+        }
+        let (width, height) = (self.logical_width - margin, self.logical_height - margin);
+        // The user coordinates of the two opposite corners; fill_rect's origin is the lower one
+        let (x0, y0) = self.to_user_coords(inset, inset);
+        let (x1, y1) = self.to_user_coords(inset + width - 1, inset + height - 1);
+        let (x, y) = (x0.min(x1), y0.min(y1));
+        for (px, py) in dashed_outline(x, y, width, height, SAFE_AREA_DASH, SAFE_AREA_DASH, 0) {
+            self.blend_pixel(px, py, color);
+        }
+    }
+
+    /// Draw a downscaled copy of an off-screen canvas into `rect`, outlining the visible part
+    ///
+    /// `src` is scaled to fill `rect` (which covers the same pixels as `fill_rect` would),
+    /// sampling the source pixel nearest each minimap pixel's center, with the source's top row
+    /// at the visual top. `viewport` selects the part of `src` currently shown elsewhere, in
+    /// source pixels counted from its top-left corner like `OwnedCanvas::get_pixel`; it maps to
+    /// the minimap pixels its edges fall in, and is outlined by inverting those pixels so the
+    /// frame stays visible over any content. The outline is clipped to the minimap
+    pub fn draw_minimap(&mut self, rect: Rect, src: &OwnedCanvas, viewport: Rect) {
+        self.modified = true;
+        if rect.width == 0 || rect.height == 0 || src.width() == 0 || src.height() == 0 {
+            return;
+        }
+        let down = self.down();
+        let (bottom, up) = self.vertical_span(rect);
+        let top = bottom + (rect.height as i32 - 1) * up;
+        let (scale_x, scale_y) = (
+            rect.width as f32 / src.width() as f32,
+            rect.height as f32 / src.height() as f32,
+        );
+
+        for row in 0..rect.height {
+            let src_y = ((row as f32 + 0.5) / scale_y) as u32;
+            for column in 0..rect.width {
+                let src_x = ((column as f32 + 0.5) / scale_x) as u32;
+                if let Some(color) = src.get_pixel(src_x, src_y) {
+                    self.set_pixel(rect.x + column as i32, top + row as i32 * down, color);
+                }
             }
         }
-        // Draw bottom edge
-        for phys_x in 0..self.physical_width {
-            self.set_physical_pixel(phys_x, self.physical_height - 1, self.grid_color);
+
+        // The viewport's edges in minimap columns and visual rows, clipped to the minimap
+        let to_minimap = |start: i32, length: u32, scale: f32, size: u32| {
+            let first = (start as f32 * scale).floor() as i32;
+            let last = ((start as f32 + length as f32) * scale).ceil() as i32 - 1;
+            (first.max(0), last.max(first).min(size as i32 - 1))
+        };
+        let (left, right) = to_minimap(viewport.x, viewport.width, scale_x, rect.width);
+        let (first_row, last_row) = to_minimap(viewport.y, viewport.height, scale_y, rect.height);
+        if viewport.width == 0 || viewport.height == 0 || left > right || first_row > last_row {
+            return;
         }
+        let outline_y = (top + first_row * down).min(top + last_row * down);
+        let width = (right - left + 1) as u32;
+        let height = (last_row - first_row + 1) as u32;
+        for (x, y) in rect_outline(rect.x + left, outline_y, width, height) {
+            if let Some((logical_x, logical_y)) = self.to_logical_coords(x, y) {
+                self.invert_logical_pixel(logical_x, logical_y);
+            }
+        }
+    }
+
+    /// Draw a pulsing 1-pixel outline around the rectangle `fill_rect` would fill
+    ///
+    /// The outline is alpha-blended over the content, with its opacity rising and falling
+    /// smoothly once per cycle of `phase` (0.0-1.0, wrapping): faintest (a quarter of
+    /// `color`'s alpha) at 0.0 and fully `color`'s alpha at 0.5. Nothing here tracks time, so the
+    /// world advances `phase` itself, either by a fixed amount per `update` step or by
+    /// `FrameStats::frame_time` divided by the pulse period
+    pub fn draw_pulse_highlight(
+        &mut self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: Color,
+        phase: f32,
+    ) {
+        self.modified = true;
+        let wave = 0.5 - 0.5 * (phase.rem_euclid(1.0) * std::f32::consts::TAU).cos();
+        let strength = 0.25 + 0.75 * wave;
+        let alpha = (color.3 as f32 * strength).round() as u8;
+        for (px, py) in rect_outline(x, y, width, height) {
+            self.blend_pixel(px, py, (color.0, color.1, color.2, alpha));
+        }
+    }
+
+    /// Draw a crosshair through (x, y) spanning the whole canvas by inverting the pixels under it
+    ///
+    /// The row and column through the point are each exactly one logical pixel wide, and every
+    /// pixel on them has its red, green and blue inverted (alpha is kept), so the crosshair
+    /// stands out on any background. The crossing pixel is inverted once. A line whose row or
+    /// column lies outside the canvas is left out. Call it last in `draw`, since later drawing
+    /// would cover it
+    pub fn draw_tracking_crosshair(&mut self, x: i32, y: i32) {
+        self.modified = true;
+        let (logical_x, logical_y) = self.to_unclipped_logical_coords(x, y);
+        let column_inside = (0..self.logical_width as i32).contains(&logical_x);
+        let row_inside = (0..self.logical_height as i32).contains(&logical_y);
+
+        if row_inside {
+            for column in 0..self.logical_width {
+                self.invert_logical_pixel(column, logical_y as u32);
+            }
+        }
+        if column_inside {
+            for row in (0..self.logical_height).filter(|&row| !row_inside || row != logical_y as u32) {
+                self.invert_logical_pixel(logical_x as u32, row);
+            }
+        }
+    }
+
+    /// Invert the red, green and blue channels of a logical pixel (in buffer coordinates)
+    fn invert_logical_pixel(&mut self, logical_x: u32, logical_y: u32) {
+        if let Some((r, g, b, a)) = self.get_logical_pixel(logical_x, logical_y) {
+            self.set_logical_pixel(logical_x, logical_y, (255 - r, 255 - g, 255 - b, a));
+        }
+    }
+
+    /// Draw a 1-pixel line from (x0, y0) to (x1, y1), including both end points
+    ///
+    /// Plots the pixels of Bresenham's algorithm, so the line is exact and contains no gaps. The
+    /// line is clipped to the canvas before it is walked, so the cost depends on the visible part
+    /// only and far-away end points neither overflow nor take long
+    pub fn draw_line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Color) {
+        self.modified = true;
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+        // Step k along the major axis moves the minor axis by Bresenham's offset
+        // floor((2 k minor + major) / (2 major)), which never decreases, so the steps that land on
+        // the canvas form one range that can be solved for directly
+        let (dx, dy) = (x1 as i128 - x0 as i128, y1 as i128 - y0 as i128);
+        let x_major = dx.abs() >= dy.abs();
+        let (major, minor) = if x_major { (dx.abs(), dy.abs()) } else { (dy.abs(), dx.abs()) };
+        // Offsets from `start` in the direction of `step` that stay within min..=max
+        let offsets = |start: i32, step: i128, min: i32, max: i32| {
+            let (low, high) = (min as i128 - start as i128, max as i128 - start as i128);
+            if step < 0 { (-high, -low) } else { (low, high) }
+        };
+        let x_offsets = offsets(x0, dx.signum(), min_x, max_x);
+        let y_offsets = offsets(y0, dy.signum(), min_y, max_y);
+        let (major_offsets, minor_offsets) = if x_major { (x_offsets, y_offsets) } else { (y_offsets, x_offsets) };
+
+        let mut first = major_offsets.0.max(0);
+        let mut last = major_offsets.1.min(major);
+        if minor == 0 {
+            if minor_offsets.0 > 0 || minor_offsets.1 < 0 {
+                return;
+            }
+        } else {
+            let ceil_div = |a: i128, b: i128| -(-a).div_euclid(b);
+            first = first.max(ceil_div(2 * minor_offsets.0 * major - major, 2 * minor));
+            last = last.min((2 * (minor_offsets.1 + 1) * major - major - 1).div_euclid(2 * minor));
+        }
+
+        for k in first..=last {
+            let offset = (2 * k * minor + major).checked_div(2 * major).unwrap_or(0);
+            let (along_x, along_y) = if x_major { (k, offset) } else { (offset, k) };
+            let x = x0 as i128 + dx.signum() * along_x;
+            let y = y0 as i128 + dy.signum() * along_y;
+            self.set_pixel(x as i32, y as i32, color);
+        }
+    }
+
+    /// Draw the 1-pixel outline of a circle of `radius` around (cx, cy)
+    ///
+    /// The outline is the rim of the pixels `point_in_circle` counts as inside, so it is
+    /// symmetric and has no gaps. A radius of 0 plots the center pixel
+    pub fn draw_circle(&mut self, cx: i32, cy: i32, radius: u32, color: Color) {
+        self.modified = true;
+        let Some((_, min_y, _, max_y)) = self.user_bounds() else {
+            return;
+        };
+        // Row by row in i64, visiting only the rows on the canvas. The outline's octant points
+        // (x, y) have x = half_width(y) ≥ y; in a row d they give the pixel at ±half_width(d) when
+        // that is at least d, and their mirror images (y, x) the run of columns k ≤ d whose own
+        // half width is d, which starts just past the half width of the next row out
+        let (center_x, center_y, r) = (cx as i64, cy as i64, radius as i64);
+        for y in (center_y - r).max(min_y as i64)..=(center_y + r).min(max_y as i64) {
+            let row = (y - center_y).unsigned_abs();
+            let Some(half_width) = circle_half_width(radius, row) else {
+                continue;
+            };
+            if half_width >= row {
+                let x = half_width as i64;
+                self.set_clipped_span(center_x - x, center_x - x, y, color);
+                self.set_clipped_span(center_x + x, center_x + x, y, color);
+            }
+            let next_row = row.checked_add(1).and_then(|next| circle_half_width(radius, next));
+            let run_start = next_row.map_or(0, |x| x + 1);
+            let run_end = half_width.min(row);
+            if run_start <= run_end {
+                let (start, end) = (run_start as i64, run_end as i64);
+                self.set_clipped_span(center_x - end, center_x - start, y, color);
+                self.set_clipped_span(center_x + start, center_x + end, y, color);
+            }
+        }
+    }
+
+    /// Fill a circle of `radius` around (cx, cy), covering exactly the area `draw_circle` outlines
+    pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: u32, color: Color) {
+        self.modified = true;
+        let Some((_, min_y, _, max_y)) = self.user_bounds() else {
+            return;
+        };
+        // In i64 so the circle's extent can't overflow, and only the rows on the canvas are visited
+        let (center_x, center_y, r) = (cx as i64, cy as i64, radius as i64);
+        for y in (center_y - r).max(min_y as i64)..=(center_y + r).min(max_y as i64) {
+            if let Some(half_width) = circle_half_width(radius, (y - center_y).unsigned_abs()) {
+                let half_width = half_width as i64;
+                self.set_clipped_span(center_x - half_width, center_x + half_width, y, color);
+            }
+        }
+    }
+
+    /// Set the pixels from x0 to x1 (inclusive) in row y, skipping the part off the canvas
+    fn set_clipped_span(&mut self, x0: i64, x1: i64, y: i64, color: Color) {
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+        if y < min_y as i64 || y > max_y as i64 {
+            return;
+        }
+        for x in x0.max(min_x as i64)..=x1.min(max_x as i64) {
+            self.set_pixel(x as i32, y as i32, color);
+        }
+    }
+
+    /// Spray a soft round brush stamp of `radius` around (cx, cy), like an airbrush
+    ///
+    /// Every pixel closer than `radius` to the center is alpha-blended with `color`, its alpha
+    /// scaled by `density` (clamped to 0.0-1.0) and by a smooth falloff from 1 at the center to
+    /// 0 at the rim, `(1 - (d / radius)²)²`. Each call blends over what is already there, so
+    /// repeated stamps at the same spot accumulate towards the full color, as when holding an
+    /// airbrush still; stamping along a mouse path gives a soft stroke
+    pub fn airbrush(&mut self, cx: i32, cy: i32, radius: u32, color: Color, density: f32) {
+        self.modified = true;
+        if radius == 0 {
+            return;
+        }
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+        let density = density.clamp(0.0, 1.0);
+        // In i64 so neither the squares nor the stamp's extent can overflow, and only the part
+        // of the stamp on the canvas is visited
+        let (cx, cy, r) = (cx as i64, cy as i64, radius as i64);
+        let radius_squared = (r * r) as f32;
+        for y in (cy - r).max(min_y as i64)..=(cy + r).min(max_y as i64) {
+            for x in (cx - r).max(min_x as i64)..=(cx + r).min(max_x as i64) {
+                let (dx, dy) = (x - cx, y - cy);
+                let distance_squared = (dx * dx + dy * dy) as f32 / radius_squared;
+                if distance_squared >= 1.0 {
+                    continue;
+                }
+                let falloff = (1.0 - distance_squared) * (1.0 - distance_squared);
+                let alpha = (color.3 as f32 * density * falloff).round() as u8;
+                self.blend_pixel(x as i32, y as i32, (color.0, color.1, color.2, alpha));
+            }
+        }
+    }
+
+    /// Draw the 1-pixel arc of a circle of `radius` around (cx, cy) between two angles
+    ///
+    /// Angles work as for `fill_sector`: radians from the positive x axis towards the positive y
+    /// axis, sweeping from `start_angle` to `end_angle` in either direction. The arc is traced
+    /// about a pixel at a time with `draw_line` between the points, so it has no gaps; arcs
+    /// longer than about a million pixels are traced in that many chords instead
+    pub fn draw_arc(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        radius: u32,
+        start_angle: f32,
+        end_angle: f32,
+        color: Color,
+    ) {
+        self.modified = true;
+        let point_at = |angle: f32| {
+            (
+                cx.saturating_add((radius as f32 * angle.cos()).round() as i32),
+                cy.saturating_add((radius as f32 * angle.sin()).round() as i32),
+            )
+        };
+        let sweep = (end_angle - start_angle).clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+        let steps = (sweep.abs() * radius as f32).ceil().clamp(1.0, MAX_CURVE_STEPS as f32) as u32;
+        let mut previous = point_at(start_angle);
+        self.set_pixel(previous.0, previous.1, color);
+        for step in 1..=steps {
+            let point = point_at(start_angle + sweep * step as f32 / steps as f32);
+            self.draw_line(previous.0, previous.1, point.0, point.1, color);
+            previous = point;
+        }
+    }
+
+    /// Draw an anti-aliased arc of a circle of `radius` around (cx, cy) with the given stroke width
+    ///
+    /// Coordinates are in user space with integer values at pixel centers, and angles work as for
+    /// `draw_arc`; a sweep of a full turn or more draws a complete ring. Each pixel's coverage is
+    /// the product of two terms, each a 1-pixel-wide linear ramp centered on an edge: the radial
+    /// term from how far the pixel center lies inside the band within `width / 2` of `radius`,
+    /// and the angular term from how far it lies inside the sweep, measured along the arc at the
+    /// pixel's distance from the center. The color's alpha is scaled by that coverage and blended
+    /// over the pixel, so the ends are cut off square and every edge fades over about one pixel
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_arc_aa(
+        &mut self,
+        cx: f32,
+        cy: f32,
+        radius: f32,
+        width: f32,
+        start_angle: f32,
+        end_angle: f32,
+        color: Color,
+    ) {
+        self.modified = true;
+        let half = width / 2.0;
+        if half <= 0.0 || radius < 0.0 {
+            return;
+        }
+        let sweep = (end_angle - start_angle).clamp(-std::f32::consts::TAU, std::f32::consts::TAU);
+        let span = sweep.abs();
+        let direction = if sweep < 0.0 { -1.0 } else { 1.0 };
+        let full_circle = span >= std::f32::consts::TAU;
+
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+        // Only visit pixels that are both in the ring's bounding box and on the canvas
+        let outer = radius + half + 1.0;
+        let clip = |center: f32, min: i32, max: i32| {
+            ((center - outer).floor().max(min as f32) as i32)..=((center + outer).ceil().min(max as f32) as i32)
+        };
+        for y in clip(cy, min_y, max_y) {
+            for x in clip(cx, min_x, max_x) {
+                let dx = x as f32 - cx;
+                let dy = y as f32 - cy;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let radial = (half + 0.5 - (distance - radius).abs()).clamp(0.0, 1.0);
+                if radial <= 0.0 {
+                    continue;
+                }
+                let angular = if full_circle {
+                    1.0
+                } else {
+                    // Signed angle into the sweep from its nearer end, negative outside it
+                    let offset =
+                        ((dy.atan2(dx) - start_angle) * direction).rem_euclid(std::f32::consts::TAU);
+                    let inside = if offset <= span {
+                        offset.min(span - offset)
+                    } else {
+                        -(offset - span).min(std::f32::consts::TAU - offset)
+                    };
+                    (inside * distance + 0.5).clamp(0.0, 1.0)
+                };
+                let alpha = (color.3 as f32 * radial * angular).round() as u8;
+                if alpha > 0 {
+                    self.blend_pixel(x, y, (color.0, color.1, color.2, alpha));
+                }
+            }
+        }
+    }
+
+    /// Draw a smooth 1-pixel curve passing through every point, as a cardinal (Catmull-Rom) spline
+    ///
+    /// Points are in user coordinates. Between each pair of neighbouring points the curve is a
+    /// cubic whose tangent at every point is `(1 - tension)` times half the vector from the point
+    /// before it to the point after it; the first and last points count as their own outer
+    /// neighbours, which clamps the end tangents to the first and last segments. A tension of
+    /// 0.0 gives the classic Catmull-Rom curve, 1.0 straight segments, and values in between
+    /// tighter curves. Each piece is traced with `draw_line` in about one step per pixel of its
+    /// length, so the curve has no gaps; pieces longer than about a million pixels are traced in
+    /// that many chords instead. Two points give a straight line and one a single pixel
+    pub fn draw_spline(&mut self, points: &[(f32, f32)], color: Color, tension: f32) {
+        self.modified = true;
+        let Some(&first) = points.first() else {
+            return;
+        };
+        let rounded = |(x, y): (f32, f32)| (x.round() as i32, y.round() as i32);
+        let mut previous = rounded(first);
+        self.set_pixel(previous.0, previous.1, color);
+
+        let scale = (1.0 - tension) / 2.0;
+        let tangent = |i: usize| {
+            let before = points[i.saturating_sub(1)];
+            let after = points[(i + 1).min(points.len() - 1)];
+            ((after.0 - before.0) * scale, (after.1 - before.1) * scale)
+        };
+        let distance =
+            |a: (f32, f32), b: (f32, f32)| ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        for i in 0..points.len() - 1 {
+            let (p0, p1) = (points[i], points[i + 1]);
+            let (m0, m1) = (tangent(i), tangent(i + 1));
+            // The equivalent Bézier control polygon bounds the length of the piece
+            let c0 = (p0.0 + m0.0 / 3.0, p0.1 + m0.1 / 3.0);
+            let c1 = (p1.0 - m1.0 / 3.0, p1.1 - m1.1 / 3.0);
+            let length = distance(p0, c0) + distance(c0, c1) + distance(c1, p1);
+            let steps = length.ceil().clamp(1.0, MAX_CURVE_STEPS as f32) as u32;
+            for step in 1..=steps {
+                let t = step as f32 / steps as f32;
+                let (t2, t3) = (t * t, t * t * t);
+                let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+                let h10 = t3 - 2.0 * t2 + t;
+                let h01 = -2.0 * t3 + 3.0 * t2;
+                let h11 = t3 - t2;
+                let point = rounded((
+                    h00 * p0.0 + h10 * m0.0 + h01 * p1.0 + h11 * m1.0,
+                    h00 * p0.1 + h10 * m0.1 + h01 * p1.1 + h11 * m1.1,
+                ));
+                self.draw_line(previous.0, previous.1, point.0, point.1, color);
+                previous = point;
+            }
+        }
+    }
+
+    /// Draw `count` concentric circle outlines around (cx, cy), evenly spaced out to `max_radius`
+    ///
+    /// The radii are `max_radius * i / count` for i = 1..=count, so the innermost ring is at
+    /// `max_radius / count` and the outermost at `max_radius`. A count of 0 draws nothing
+    pub fn draw_rings(&mut self, cx: i32, cy: i32, max_radius: u32, count: u32, color: Color) {
+        self.modified = true;
+        for i in 1..=count {
+            self.draw_circle(cx, cy, ring_radius(max_radius, i, count), color);
+        }
+    }
+
+    /// Fill `count` concentric bands around (cx, cy) in alternating colors, like a bullseye
+    ///
+    /// Uses the same radii as `draw_rings`. The outermost band gets `outer_color` and the colors
+    /// alternate inwards. A count of 0 draws nothing
+    pub fn fill_rings(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        max_radius: u32,
+        count: u32,
+        outer_color: Color,
+        alternate_color: Color,
+    ) {
+        self.modified = true;
+        // Largest first, so each smaller disc paints over the middle of the previous one
+        for (band, i) in (1..=count).rev().enumerate() {
+            let color = if band % 2 == 0 { outer_color } else { alternate_color };
+            self.fill_circle(cx, cy, ring_radius(max_radius, i, count), color);
+        }
+    }
+
+    /// Draw a spiral around (cx, cy), making `turns` full turns out to `max_radius`
+    ///
+    /// The angle starts at 0 along the positive x axis and increases towards the positive y axis
+    /// as with `fill_sector`. An `Archimedean` spiral starts at the center and a `Logarithmic`
+    /// one at radius 1 (it falls back to the Archimedean shape when `max_radius` is below 1).
+    /// Points along the curve are joined with `draw_line`, stepping about a pixel of the outer
+    /// turn at a time so it stays smooth, up to about a million segments in total. Nothing is
+    /// drawn for a non-finite `turns` or `max_radius`
+    pub fn draw_spiral(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        turns: f32,
+        max_radius: f32,
+        kind: SpiralKind,
+        color: Color,
+    ) {
+        self.modified = true;
+        let max_angle = turns.max(0.0) * std::f32::consts::TAU;
+        if !max_angle.is_finite() || !max_radius.is_finite() {
+            return;
+        }
+        if max_angle == 0.0 || max_radius <= 0.0 {
+            self.set_pixel(cx, cy, color);
+            return;
+        }
+        let radius_at = |angle: f32| {
+            let t = angle / max_angle;
+            match kind {
+                SpiralKind::Logarithmic if max_radius >= 1.0 => max_radius.powf(t),
+                _ => max_radius * t,
+            }
+        };
+        let point_at = |angle: f32| {
+            let radius = radius_at(angle);
+            (
+                cx + (radius * angle.cos()).round() as i32,
+                cy + (radius * angle.sin()).round() as i32,
+            )
+        };
+
+        // About one pixel of arc at the outer radius, where the curve moves fastest
+        let pixel_step = (1.0 / max_radius.max(1.0)).min(0.5);
+        let steps = (max_angle / pixel_step).ceil().clamp(1.0, MAX_CURVE_STEPS as f32) as u32;
+        let step = max_angle / steps as f32;
+        let mut previous = point_at(0.0);
+        for i in 1..=steps {
+            let point = point_at(i as f32 * step);
+            self.draw_line(previous.0, previous.1, point.0, point.1, color);
+            previous = point;
+        }
+    }
+
+    /// Draw x and y axes through `origin` with tick marks every `tick_spacing` pixels
+    ///
+    /// The x axis spans `x_range` (inclusive) along y = origin.1 and the y axis spans `y_range`
+    /// along x = origin.0, all in user coordinates. Ticks are placed at whole multiples of
+    /// `tick_spacing` from the origin, extend one pixel to each side of the axis, and skip the
+    /// origin itself. A spacing of 0 draws no ticks.
+    pub fn draw_axes(
+        &mut self,
+        origin: (i32, i32),
+        x_range: (i32, i32),
+        y_range: (i32, i32),
+        tick_spacing: u32,
+        color: Color,
+    ) {
+        self.modified = true;
+        self.draw_axes_impl(origin, x_range, y_range, tick_spacing, color, false);
+    }
+
+    /// Like `draw_axes`, and also label each tick with its distance from the origin
+    ///
+    /// x labels are centered below their ticks and y labels sit to the left of theirs
+    pub fn draw_axes_with_labels(
+        &mut self,
+        origin: (i32, i32),
+        x_range: (i32, i32),
+        y_range: (i32, i32),
+        tick_spacing: u32,
+        color: Color,
+    ) {
+        self.modified = true;
+        self.draw_axes_impl(origin, x_range, y_range, tick_spacing, color, true);
+    }
+
+    fn draw_axes_impl(
+        &mut self,
+        origin: (i32, i32),
+        x_range: (i32, i32),
+        y_range: (i32, i32),
+        tick_spacing: u32,
+        color: Color,
+        labels: bool,
+    ) {
+        let (x_min, x_max) = (x_range.0.min(x_range.1), x_range.0.max(x_range.1));
+        let (y_min, y_max) = (y_range.0.min(y_range.1), y_range.0.max(y_range.1));
+        self.draw_line(x_min, origin.1, x_max, origin.1, color);
+        self.draw_line(origin.0, y_min, origin.0, y_max, color);
+        if tick_spacing == 0 {
+            return;
+        }
+
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+        // Only ticks whose mark or label can reach the canvas are visited; no distance label is
+        // longer than the 11 characters of i32::MIN
+        let margin = if labels { self.text_width(&i32::MIN.to_string()) as i32 } else { 0 };
+        let visible = |min: i32, max: i32, canvas_min: i32, canvas_max: i32| {
+            (min.max(canvas_min.saturating_sub(margin)), max.min(canvas_max.saturating_add(margin)))
+        };
+        let (x_min, x_max) = visible(x_min, x_max, min_x, max_x);
+        let (y_min, y_max) = visible(y_min, y_max, min_y, max_y);
+        // Labels are only drawn when the axis is close enough for them to show, which also keeps
+        // their positions from overflowing
+        let near = |value: i32, min: i32, max: i32| {
+            labels && value >= min.saturating_sub(margin + 3) && value <= max.saturating_add(margin + 3)
+        };
+        let (x_labels, y_labels) = (near(origin.1, min_y, max_y), near(origin.0, min_x, max_x));
+
+        let down = self.down();
+        for x in axis_ticks(origin.0, x_min, x_max, tick_spacing) {
+            self.draw_line(x, origin.1.saturating_sub(1), x, origin.1.saturating_add(1), color);
+            if x_labels {
+                let text = (x as i64 - origin.0 as i64).to_string();
+                let label_x = x - self.text_width(&text) as i32 / 2;
+                self.draw_text(label_x, origin.1 + 3 * down, &text, color);
+            }
+        }
+        for y in axis_ticks(origin.1, y_min, y_max, tick_spacing) {
+            self.draw_line(origin.0.saturating_sub(1), y, origin.0.saturating_add(1), y, color);
+            if y_labels {
+                let text = (y as i64 - origin.1 as i64).to_string();
+                let label_x = origin.0 - 3 - self.text_width(&text) as i32;
+                let label_y = y - (GLYPH_HEIGHT as i32 / 2) * down;
+                self.draw_text(label_x, label_y, &text, color);
+            }
+        }
+    }
+
+    /// Draw a color scale legend for values from `min` to `max` mapped through a colormap
+    ///
+    /// `rect` (covering the same pixels as `fill_rect`) is filled with the gradient, running
+    /// bottom to top for `Orientation::Vertical` and left to right for `Horizontal`, with `min`
+    /// at the start and `max` at the end; the value v is colored `colormap.sample(t)` for
+    /// t = (v - min) / (max - min), so the colormap should span 0 to 1 like the presets.
+    /// `ticks` evenly spaced values from `min` to `max` inclusive (just `min` for 1) get a 2-pixel
+    /// tick outside the rectangle and a label in `color`: to the right and vertically centered
+    /// for a vertical legend, or centered below for a horizontal one. Labels show whole numbers
+    /// as such and other values with up to two decimals
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_colormap_legend(
+        &mut self,
+        rect: Rect,
+        colormap: &Colormap,
+        min: f32,
+        max: f32,
+        ticks: u32,
+        color: Color,
+        orientation: Orientation,
+    ) {
+        self.modified = true;
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+        let (bottom, up) = self.vertical_span(rect);
+        let down = -up;
+        let length = match orientation {
+            Orientation::Horizontal => rect.width,
+            Orientation::Vertical => rect.height,
+        };
+        for step in 0..length {
+            let t = if length > 1 { step as f32 / (length - 1) as f32 } else { 0.0 };
+            let band = colormap.sample(t);
+            match orientation {
+                Orientation::Horizontal => {
+                    self.fill_rect(rect.x + step as i32, rect.y, 1, rect.height, band);
+                }
+                Orientation::Vertical => {
+                    self.fill_rect(rect.x, bottom + step as i32 * up, rect.width, 1, band);
+                }
+            }
+        }
+
+        let right = rect.x + rect.width as i32 - 1;
+        for tick in 0..ticks {
+            let t = if ticks > 1 { tick as f32 / (ticks - 1) as f32 } else { 0.0 };
+            let text = format_tick_label(min + (max - min) * t);
+            let step = (t * (length - 1) as f32).round() as i32;
+            match orientation {
+                Orientation::Horizontal => {
+                    let x = rect.x + step;
+                    self.draw_line(x, bottom + down, x, bottom + 2 * down, color);
+                    let label_x = x - self.text_width(&text) as i32 / 2;
+                    self.draw_text(label_x, bottom + 4 * down, &text, color);
+                }
+                Orientation::Vertical => {
+                    let y = bottom + step * up;
+                    self.draw_line(right + 1, y, right + 2, y, color);
+                    let label_y = y + (GLYPH_HEIGHT as i32 / 2) * up;
+                    self.draw_text(right + 4, label_y, &text, color);
+                }
+            }
+        }
+    }
+
+    /// Draw a progress bar or meter filling `rect` (covering the same pixels as `fill_rect`)
+    ///
+    /// The background is filled with `bg`, and with a `border` the outermost pixels become a
+    /// 1-pixel outline in that color, leaving the interior inside it. `progress` (clamped to
+    /// 0.0-1.0) of the interior's width is then filled with `fg` from the left for
+    /// `Orientation::Horizontal`, or of its height from the bottom for `Vertical`, rounded to
+    /// the nearest pixel
+    pub fn draw_progress_bar(
+        &mut self,
+        rect: Rect,
+        progress: f32,
+        fg: Color,
+        bg: Color,
+        border: Option<Color>,
+        orientation: Orientation,
+    ) {
+        self.modified = true;
+        self.fill_rect(rect.x, rect.y, rect.width, rect.height, bg);
+        let interior = match border {
+            Some(border) => {
+                for (x, y) in rect_outline(rect.x, rect.y, rect.width, rect.height) {
+                    self.set_pixel(x, y, border);
+                }
+                let (width, height) = (rect.width.saturating_sub(2), rect.height.saturating_sub(2));
+                Rect::new(rect.x + 1, rect.y + 1, width, height)
+            }
+            None => rect,
+        };
+        if interior.width == 0 || interior.height == 0 {
+            return;
+        }
+
+        let progress = if progress.is_nan() { 0.0 } else { progress.clamp(0.0, 1.0) };
+        match orientation {
+            Orientation::Horizontal => {
+                let filled = (progress * interior.width as f32).round() as u32;
+                self.fill_rect(interior.x, interior.y, filled, interior.height, fg);
+            }
+            Orientation::Vertical => {
+                let filled = (progress * interior.height as f32).round() as u32;
+                if filled > 0 {
+                    let (bottom, up) = self.vertical_span(interior);
+                    let top = bottom + (filled as i32 - 1) * up;
+                    self.fill_visual_rect(interior.x, top, interior.width, filled, fg);
+                }
+            }
+        }
+    }
+
+    /// Fill a grid of `cols`×`rows` cells, each with its own color
+    ///
+    /// The top-left cell's top-left pixel is at (x, y); columns extend right and rows extend
+    /// visually downward, with `gap` pixels left untouched between neighbouring cells. Cell
+    /// (col, row) is filled with `colors[row * cols + col]`.
+    ///
+    /// Returns false without drawing anything if `colors.len()` is not `cols * rows`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_cell_grid(
+        &mut self,
+        x: i32,
+        y: i32,
+        cols: u32,
+        rows: u32,
+        cell_w: u32,
+        cell_h: u32,
+        colors: &[Color],
+        gap: u32,
+    ) -> bool {
+        self.modified = true;
+        if cols.checked_mul(rows).map(|cells| cells as usize) != Some(colors.len()) {
+            return false;
+        }
+        let down = self.down();
+        for row in 0..rows {
+            let cell_y = y + (row * (cell_h + gap)) as i32 * down;
+            for col in 0..cols {
+                let cell_x = x + (col * (cell_w + gap)) as i32;
+                let color = colors[(row * cols + col) as usize];
+                self.fill_visual_rect(cell_x, cell_y, cell_w, cell_h, color);
+            }
+        }
+        true
+    }
+
+    /// Draw a node-link graph: every edge, then every node as a filled circle with its label
+    ///
+    /// Edges run between node centers and are drawn first so the circles cover their ends. Edges
+    /// referring to a node index that doesn't exist are skipped. Each non-empty label is centered
+    /// horizontally on its node with its top line starting 2 pixels below the circle
+    pub fn draw_graph(&mut self, graph: &Graph) {
+        self.modified = true;
+        for &(from, to) in &graph.edges {
+            let (Some(from), Some(to)) = (graph.nodes.get(from), graph.nodes.get(to)) else {
+                continue;
+            };
+            let (from, to) = (from.position, to.position);
+            match graph.edge_style {
+                EdgeStyle::Straight => self.draw_line(from.0, from.1, to.0, to.1, graph.edge_color),
+                EdgeStyle::Elbow { corner_radius } => {
+                    self.draw_elbow_connector(from, to, corner_radius, graph.edge_color)
+                }
+            }
+        }
+
+        let label_offset = (graph.node_radius + 2) as i32 * self.down();
+        for node in &graph.nodes {
+            let (x, y) = node.position;
+            self.fill_circle(x, y, graph.node_radius, node.color);
+            if !node.label.is_empty() {
+                let label_x = x - self.text_width(&node.label) as i32 / 2;
+                self.draw_text(label_x, y + label_offset, &node.label, graph.label_color);
+            }
+        }
+    }
+
+    /// Connect two points with a right-angled route: horizontally from `from`, then vertically
+    /// to `to`, rounding the corner with an arc of `corner_radius`
+    ///
+    /// The radius is limited to the shorter of the two legs, and 0 gives a sharp corner. Points
+    /// on the same row or column are joined by a straight line
+    pub fn draw_elbow_connector(
+        &mut self,
+        from: (i32, i32),
+        to: (i32, i32),
+        corner_radius: u32,
+        color: Color,
+    ) {
+        self.modified = true;
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        if dx == 0 || dy == 0 {
+            self.draw_line(from.0, from.1, to.0, to.1, color);
+            return;
+        }
+        let (sx, sy) = (dx.signum(), dy.signum());
+        let radius = (corner_radius as i32).min(dx.abs()).min(dy.abs());
+        let corner = (to.0, from.1);
+
+        self.draw_line(from.0, from.1, corner.0 - sx * radius, corner.1, color);
+        self.draw_line(corner.0, corner.1 + sy * radius, to.0, to.1, color);
+        if radius > 0 {
+            // The arc's center sits diagonally inside the corner; take the quarter turn between
+            // the end of the horizontal leg and the start of the vertical one
+            let center = (corner.0 - sx * radius, corner.1 + sy * radius);
+            let start = (-sy as f32).atan2(0.0);
+            let mut end = 0.0f32.atan2(sx as f32);
+            if end - start > std::f32::consts::PI {
+                end -= std::f32::consts::TAU;
+            } else if end - start < -std::f32::consts::PI {
+                end += std::f32::consts::TAU;
+            }
+            self.draw_arc(center.0, center.1, radius as u32, start, end, color);
+        }
+    }
+
+    /// Draw an arrow from `from` to `to` with an arrowhead at `to`
+    ///
+    /// The shaft is exactly `draw_line` between the two points, and the head is two strokes of
+    /// `head_size` pixels angled back from the tip. A zero-length arrow plots a single point
+    pub fn draw_arrow(&mut self, from: (i32, i32), to: (i32, i32), head_size: u32, color: Color) {
+        self.modified = true;
+        self.draw_line(from.0, from.1, to.0, to.1, color);
+        self.draw_arrowhead(from, to, head_size, color);
+    }
+
+    /// Draw one arrow per cell of a `cols`×`rows` grid laid over `rect`, like a flow field
+    ///
+    /// Each arrow starts at its cell's center and points along the cell's vector, which is in
+    /// user coordinates and multiplied by `scale` to get the arrow in pixels. Rows run visually
+    /// downward, so cell (col, row) uses `vectors[row * cols + col]` as in `draw_cell_grid`.
+    /// Vectors too short to leave the center pixel are drawn as a single dot.
+    ///
+    /// Returns false without drawing anything if `vectors.len()` is not `cols * rows`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_vector_field(
+        &mut self,
+        rect: Rect,
+        cols: u32,
+        rows: u32,
+        vectors: &[(f32, f32)],
+        scale: f32,
+        color: Color,
+    ) -> bool {
+        self.modified = true;
+        if cols.checked_mul(rows).map(|cells| cells as usize) != Some(vectors.len()) {
+            return false;
+        }
+        let (bottom, up) = self.vertical_span(rect);
+        let top = bottom + (rect.height as i32 - 1) * up;
+        for row in 0..rows {
+            let center_y = top - ((2 * row + 1) * rect.height / (2 * rows)) as i32 * up;
+            for col in 0..cols {
+                let center_x = rect.x + ((2 * col + 1) * rect.width / (2 * cols)) as i32;
+                let (vx, vy) = vectors[(row * cols + col) as usize];
+                let tip = (
+                    center_x + (vx * scale).round() as i32,
+                    center_y + (vy * scale).round() as i32,
+                );
+                self.draw_arrow((center_x, center_y), tip, FIELD_ARROWHEAD_SIZE, color);
+            }
+        }
+        true
+    }
+
+    /// Draw the isoline where a sampled scalar field crosses `level`, using marching squares
+    ///
+    /// `values` holds a `cols`×`rows` grid of samples in row-major order. Sample (col, row) lies
+    /// `col * cell_size` pixels right of and `row * cell_size` pixels visually below (x, y), so
+    /// (x, y) is the top-left sample as in `draw_sprite`. Samples at or above `level` count as
+    /// inside. In every cell whose corners are not all on the same side, the crossing points are
+    /// linearly interpolated along its edges and joined with `draw_line`. A saddle cell
+    /// (opposite corners inside) is resolved by the average of its four corners: when that is
+    /// inside, the two inside corners are joined through the middle of the cell.
+    ///
+    /// Returns false without drawing anything if `values.len()` is not `cols * rows`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_contour(
+        &mut self,
+        x: i32,
+        y: i32,
+        cols: u32,
+        rows: u32,
+        values: &[f32],
+        level: f32,
+        cell_size: u32,
+        color: Color,
+    ) -> bool {
+        self.modified = true;
+        if cols.checked_mul(rows).map(|samples| samples as usize) != Some(values.len()) {
+            return false;
+        }
+        let down = self.down();
+        let to_user = |(gx, gy): (f32, f32)| {
+            (
+                x + (gx * cell_size as f32).round() as i32,
+                y + (gy * cell_size as f32).round() as i32 * down,
+            )
+        };
+        for (from, to) in contour_segments(cols, rows, values, level) {
+            let (from, to) = (to_user(from), to_user(to));
+            self.draw_line(from.0, from.1, to.0, to.1, color);
+        }
+        true
+    }
+
+    /// Fill the bands between contour levels of a sampled scalar field with colormap colors
+    ///
+    /// The grid is laid out as in `draw_contour`, and every pixel from the top-left sample to
+    /// the bottom-right one is filled. `levels` (in ascending order) split the values into
+    /// `levels.len() + 1` bands: band i holds the values reached by exactly i levels, and is
+    /// colored `colormap.sample(i / levels.len())`, so the lowest band takes the map's start
+    /// and the highest its end.
+    ///
+    /// Between samples the field is assumed piecewise linear: each cell is split into four
+    /// triangles meeting at its center, which takes the average of the four corners, and the
+    /// value is interpolated linearly within each triangle. Along the cell edges the band
+    /// boundaries therefore fall exactly on the crossings `draw_contour` draws, and saddle
+    /// cells are resolved by the same center average. Every pixel is evaluated individually,
+    /// giving the filled marching-squares cases without gaps or overlaps between cells.
+    ///
+    /// Returns false without drawing anything if `values.len()` is not `cols * rows`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_filled_contours(
+        &mut self,
+        x: i32,
+        y: i32,
+        cols: u32,
+        rows: u32,
+        values: &[f32],
+        levels: &[f32],
+        colormap: &Colormap,
+        cell_size: u32,
+    ) -> bool {
+        self.modified = true;
+        if cols.checked_mul(rows).map(|samples| samples as usize) != Some(values.len()) {
+            return false;
+        }
+        if cols < 2 || rows < 2 || cell_size == 0 {
+            return true;
+        }
+        let colors: Vec<Color> = (0..=levels.len())
+            .map(|band| colormap.sample(band as f32 / levels.len().max(1) as f32))
+            .collect();
+        let down = self.down();
+        let sample = |c: u32, r: u32| values[(r * cols + c) as usize];
+        for dy in 0..=(rows - 1) * cell_size {
+            let row = (dy / cell_size).min(rows - 2);
+            let v = (dy - row * cell_size) as f32 / cell_size as f32;
+            for dx in 0..=(cols - 1) * cell_size {
+                let col = (dx / cell_size).min(cols - 2);
+                let u = (dx - col * cell_size) as f32 / cell_size as f32;
+                let corners = [
+                    sample(col, row),
+                    sample(col + 1, row),
+                    sample(col + 1, row + 1),
+                    sample(col, row + 1),
+                ];
+                let value = cell_value(corners, u, v);
+                let band = levels.iter().filter(|&&level| value >= level).count();
+                self.set_pixel(x + dx as i32, y + dy as i32 * down, colors[band]);
+            }
+        }
+        true
+    }
+
+    /// Draw the two strokes of an arrowhead at `tip` for a shaft coming from `from`
+    ///
+    /// The strokes are `size` pixels long (never longer than the shaft) and angled 30° off the
+    /// shaft. Nothing is drawn for a zero-length shaft
+    fn draw_arrowhead(&mut self, from: (i32, i32), tip: (i32, i32), size: u32, color: Color) {
+        let (dx, dy) = ((from.0 - tip.0) as f32, (from.1 - tip.1) as f32);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return;
+        }
+        let size = (size as f32).min(length);
+        let (unit_x, unit_y) = (dx / length, dy / length);
+        let (sin, cos) = std::f32::consts::FRAC_PI_6.sin_cos();
+        for side in [-1.0, 1.0] {
+            let wing_x = unit_x * cos - unit_y * sin * side;
+            let wing_y = unit_x * sin * side + unit_y * cos;
+            let end_x = tip.0 + (wing_x * size).round() as i32;
+            let end_y = tip.1 + (wing_y * size).round() as i32;
+            self.draw_line(tip.0, tip.1, end_x, end_y, color);
+        }
+    }
+
+    /// Fill a rectangle whose top-left pixel, as seen on screen, is at (x, y)
+    ///
+    /// Same as `fill_rect` in `TopLeft` coordinates; in `Center` coordinates the rectangle
+    /// extends downward from (x, y) instead of upward
+    fn fill_visual_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        match self.coordinate_system {
+            CoordinateSystem::TopLeft => self.fill_rect(x, y, width, height, color),
+            CoordinateSystem::Center => self.fill_rect(x, y - height as i32 + 1, width, height, color),
+        }
+    }
+
+    /// Encode the physical pixels of this canvas as PNG bytes
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> std::io::Result<Vec<u8>> {
+        let rgba: Vec<u8> = self.physical_chunks().flatten().copied().collect();
+        crate::png::encode_png(self.physical_width, self.physical_height, &rgba)
+    }
+
+    /// Encode the physical pixels as a `data:image/png;base64,...` URI
+    ///
+    /// Handy for embedding frames in HTML reports or sending them over a socket without touching
+    /// the filesystem
+    #[cfg(feature = "png")]
+    pub fn to_png_base64(&self) -> std::io::Result<String> {
+        Ok(format!("data:image/png;base64,{}", crate::png::encode_base64(&self.to_png()?)))
+    }
+
+    /// Downsample the physical buffer to a `width`×`height` RGBA image
+    ///
+    /// Every output pixel is the average of the block of source pixels it covers, with source
+    /// pixels that are only partly covered (when the sizes aren't whole multiples) weighted by the
+    /// covered fraction. Channels are averaged independently, alpha included. The result is
+    /// tightly packed, row by row from the top, and empty if either size is 0
+    pub fn thumbnail(&self, width: u32, height: u32) -> Vec<u8> {
+        if width == 0 || height == 0 || self.physical_width == 0 || self.physical_height == 0 {
+            return Vec::new();
+        }
+        let scale_x = self.physical_width as f32 / width as f32;
+        let scale_y = self.physical_height as f32 / height as f32;
+        // Source pixels overlapping [start, end) along one axis, with how much of each is covered
+        let coverage = |start: f32, end: f32| {
+            (start.floor() as u32..end.ceil() as u32)
+                .map(move |i| (i, end.min(i as f32 + 1.0) - start.max(i as f32)))
+        };
+
+        let mut thumbnail = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0f32; 4];
+                let mut total = 0.0;
+                for (src_y, weight_y) in coverage(y as f32 * scale_y, (y + 1) as f32 * scale_y) {
+                    for (src_x, weight_x) in coverage(x as f32 * scale_x, (x + 1) as f32 * scale_x) {
+                        if let Some(color) = self.get_physical_pixel(src_x, src_y) {
+                            let weight = weight_x * weight_y;
+                            sum[0] += color.0 as f32 * weight;
+                            sum[1] += color.1 as f32 * weight;
+                            sum[2] += color.2 as f32 * weight;
+                            sum[3] += color.3 as f32 * weight;
+                            total += weight;
+                        }
+                    }
+                }
+                let total = if total > 0.0 { total } else { 1.0 };
+                thumbnail.extend(sum.iter().map(|channel| (channel / total).round() as u8));
+            }
+        }
+        thumbnail
+    }
+
+    /// Count how often each value occurs in each channel of the physical buffer
+    ///
+    /// Returns one 256-bin histogram per channel, indexed R, G, B, A (see `Channel::index`)
+    pub fn histogram(&self) -> [[u32; 256]; 4] {
+        let mut bins = [[0; 256]; 4];
+        for chunk in self.physical_chunks() {
+            for (channel, value) in chunk.iter().enumerate() {
+                bins[channel][*value as usize] += 1;
+            }
+        }
+        bins
+    }
+
+    /// Draw a bar chart of `values` filling the given rectangle
+    ///
+    /// Bars grow from the visual bottom of the rectangle and are scaled so that the largest value
+    /// fills its full height. When there are more values than columns, each column shows the
+    /// largest of the values it covers. Negative values are drawn as empty bars.
+    pub fn draw_bar_chart(&mut self, rect: Rect, values: &[f32], color: Color) {
+        self.modified = true;
+        if values.is_empty() || rect.width == 0 || rect.height == 0 {
+            return;
+        }
+        let max = values.iter().cloned().fold(0.0, f32::max);
+        if max <= 0.0 {
+            return;
+        }
+
+        let (bottom, up) = self.vertical_span(rect);
+        let count = values.len() as u32;
+        for column in 0..rect.width {
+            let start = (column * count / rect.width) as usize;
+            let end = (((column + 1) * count).div_ceil(rect.width) as usize).max(start + 1);
+            let value = values[start..end].iter().cloned().fold(0.0, f32::max);
+            let bar_height = ((value / max) * rect.height as f32).round() as i32;
+            for row in 0..bar_height {
+                self.set_pixel(rect.x + column as i32, bottom + row * up, color);
+            }
+        }
+    }
+
+    /// Draw the histogram of a single channel as a bar chart filling the given rectangle
+    ///
+    /// Bars are drawn in the channel's own color (white for alpha)
+    pub fn draw_histogram(&mut self, rect: Rect, channel: Channel) {
+        self.modified = true;
+        let bins = self.histogram()[channel.index()];
+        let values: Vec<f32> = bins.iter().map(|&count| count as f32).collect();
+        let color = match channel {
+            Channel::Red => (255, 0, 0, 255),
+            Channel::Green => (0, 255, 0, 255),
+            Channel::Blue => (0, 0, 255, 255),
+            Channel::Alpha => (255, 255, 255, 255),
+        };
+        self.draw_bar_chart(rect, &values, color);
+    }
+
+    /// Fill the circular sector of `radius` around (cx, cy) between two angles
+    ///
+    /// Angles are in radians, measured from the positive x axis towards the positive y axis of
+    /// the user coordinate system, so they run counter-clockwise on screen in `Center` and
+    /// clockwise in `TopLeft`. The sector sweeps from `start_angle` to `end_angle`, in either
+    /// direction; a sweep of a full turn or more fills the whole disc. A pixel is inside when it
+    /// is in the circle `fill_circle` fills (see `point_in_circle`) and its direction lies
+    /// within the sweep, so a full set of sectors lines up with the filled circle.
+    pub fn fill_sector(&mut self, cx: i32, cy: i32, radius: u32, start_angle: f32, end_angle: f32, color: Color) {
+        self.modified = true;
+        let (start, sweep) = if end_angle >= start_angle {
+            (start_angle, end_angle - start_angle)
+        } else {
+            (end_angle, start_angle - end_angle)
+        };
+        let full_circle = sweep >= std::f32::consts::TAU;
+        let Some((min_x, min_y, max_x, max_y)) = self.user_bounds() else {
+            return;
+        };
+
+        // In i64 so the disc's extent can't overflow, and only the part on the canvas is visited
+        let (center_x, center_y, r) = (cx as i64, cy as i64, radius as i64);
+        for y in (center_y - r).max(min_y as i64)..=(center_y + r).min(max_y as i64) {
+            for x in (center_x - r).max(min_x as i64)..=(center_x + r).min(max_x as i64) {
+                let (x, y) = (x as i32, y as i32);
+                if !point_in_circle(x, y, cx, cy, radius) {
+                    continue;
+                }
+                let (dx, dy) = (x as i64 - center_x, y as i64 - center_y);
+                let inside = full_circle || (dx == 0 && dy == 0) || {
+                    let angle = (dy as f32).atan2(dx as f32);
+                    let offset = (angle - start).rem_euclid(std::f32::consts::TAU);
+                    offset <= sweep + 1e-4
+                };
+                if inside {
+                    self.set_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Draw a pie chart of `values` as sectors of a disc around (cx, cy)
+    ///
+    /// Each value gets a slice proportional to its share of the total, starting at angle 0 and
+    /// continuing in the direction of increasing angle (see `fill_sector`). Slices cycle through
+    /// `colors`. Negative values count as zero, and nothing is drawn when the values sum to zero
+    /// or no colors are given
+    pub fn draw_pie_chart(&mut self, cx: i32, cy: i32, radius: u32, values: &[f32], colors: &[Color]) {
+        self.modified = true;
+        let total: f32 = values.iter().map(|value| value.max(0.0)).sum();
+        if total <= 0.0 || colors.is_empty() {
+            return;
+        }
+
+        let mut start = 0.0;
+        for (i, value) in values.iter().enumerate() {
+            let share = value.max(0.0) / total;
+            if share <= 0.0 {
+                continue;
+            }
+            let end = start + share * std::f32::consts::TAU;
+            self.fill_sector(cx, cy, radius, start, end, colors[i % colors.len()]);
+            start = end;
+        }
+    }
+
+    /// The user y coordinate of the visual bottom row of a rectangle, and the y step that moves up
+    fn vertical_span(&self, rect: Rect) -> (i32, i32) {
+        match self.coordinate_system {
+            CoordinateSystem::TopLeft => (rect.y + rect.height as i32 - 1, -1),
+            CoordinateSystem::Center => (rect.y, 1),
+        }
+    }
+
+    /// Binarize the physical buffer by luminance
+    ///
+    /// Every pixel whose luminance (see `luminance`) is below `level` becomes `below`, all others
+    /// become `above`
+    pub fn threshold(&mut self, level: u8, below: Color, above: Color) {
+        self.modified = true;
+        for chunk in self.physical_chunks_mut() {
+            let color = (chunk[0], chunk[1], chunk[2], chunk[3]);
+            let replacement = if luminance(color) < level { below } else { above };
+            chunk[0] = replacement.0;
+            chunk[1] = replacement.1;
+            chunk[2] = replacement.2;
+            chunk[3] = replacement.3;
+        }
+    }
+
+    /// Remap the red, green and blue channels of every physical pixel through a lookup table
+    ///
+    /// Alpha is left untouched, and an identity table leaves the canvas unchanged
+    pub fn apply_lut(&mut self, lut: &ColorLut) {
+        self.modified = true;
+        for chunk in self.physical_chunks_mut() {
+            let (r, g, b, _) = lut.map((chunk[0], chunk[1], chunk[2], chunk[3]));
+            chunk[0] = r;
+            chunk[1] = g;
+            chunk[2] = b;
+        }
+    }
+
+    /// Recolor every physical pixel as it would appear with a color-vision deficiency
+    ///
+    /// Each color is converted from sRGB to linear light, transformed by the deficiency's
+    /// matrix (derived from a model of the cone responses in LMS space) and converted back, with
+    /// the result clamped to the displayable range. Every row of the matrices sums to 1, so
+    /// grays, black and white are unchanged. Alpha is left untouched. Call it at the end of
+    /// `draw` to preview the whole frame, for example behind a key toggle
+    pub fn simulate_cvd(&mut self, kind: CvdKind) {
+        self.modified = true;
+        let matrix = kind.matrix();
+        let to_linear: [f32; 256] = std::array::from_fn(|value| {
+            let value = value as f32 / 255.0;
+            if value <= 0.04045 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        let to_srgb = |value: f32| {
+            let value = value.clamp(0.0, 1.0);
+            let encoded = if value <= 0.0031308 {
+                value * 12.92
+            } else {
+                1.055 * value.powf(1.0 / 2.4) - 0.055
+            };
+            (encoded * 255.0).round() as u8
+        };
+        for chunk in self.physical_chunks_mut() {
+            let rgb = [0, 1, 2].map(|channel| to_linear[chunk[channel] as usize]);
+            for (channel, row) in chunk.iter_mut().zip(matrix) {
+                *channel = to_srgb(row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+            }
+        }
+    }
+
+    /// Darken everything outside a circular region around (cx, cy)
+    ///
+    /// Pixels within `radius` logical pixels of the center keep their brightness. Beyond that the
+    /// RGB channels are scaled down linearly over the next `falloff` logical pixels until they
+    /// reach `darkness` (0.0 = black, 1.0 = unchanged), which then applies to the rest of the
+    /// canvas. Alpha is left untouched.
+    ///
+    /// This is a pass over the whole physical buffer, so call it after everything it should
+    /// affect has been drawn
+    pub fn apply_spotlight(&mut self, cx: i32, cy: i32, radius: f32, falloff: f32, darkness: f32) {
+        self.modified = true;
+        let darkness = darkness.clamp(0.0, 1.0);
+        let (center_x, center_y) = self.to_unclipped_logical_coords(cx, cy);
+        let center_x = center_x as f32 + 0.5;
+        let center_y = center_y as f32 + 0.5;
+
+        for phys_y in 0..self.physical_height {
+            let logical_y = (phys_y as f32 + 0.5) / self.pixel_scale_y as f32;
+            for phys_x in 0..self.physical_width {
+                let logical_x = (phys_x as f32 + 0.5) / self.pixel_scale_x as f32;
+                let distance = ((logical_x - center_x).powi(2) + (logical_y - center_y).powi(2)).sqrt();
+
+                let factor = if distance <= radius {
+                    continue;
+                } else if falloff > 0.0 && distance < radius + falloff {
+                    let t = (distance - radius) / falloff;
+                    1.0 + (darkness - 1.0) * t
+                } else {
+                    darkness
+                };
+
+                if let Some(color) = self.get_physical_pixel(phys_x, phys_y) {
+                    let scale = |c: u8| (c as f32 * factor).round() as u8;
+                    self.set_physical_pixel(
+                        phys_x,
+                        phys_y,
+                        (scale(color.0), scale(color.1), scale(color.2), color.3),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Draw a sprite with its top-left pixel at (x, y)
+    ///
+    /// The sprite extends right and visually downward in every coordinate system. Pixels equal
+    /// to `color_key` are skipped, letting the content underneath show through
+    pub fn draw_sprite(&mut self, x: i32, y: i32, sprite: &Sprite, color_key: Option<Color>) {
+        self.modified = true;
+        self.draw_sprite_region(x, y, &sprite.full_region(), color_key);
+    }
+
+    /// Draw part of a sprite (such as one tile of a sheet) with its top-left pixel at (x, y)
+    ///
+    /// Behaves like `draw_sprite` restricted to the region
+    pub fn draw_sprite_region(&mut self, x: i32, y: i32, region: &SpriteRegion, color_key: Option<Color>) {
+        self.modified = true;
+        let down = self.down();
+        for row in 0..region.height() {
+            for column in 0..region.width() {
+                if let Some(color) = region.get_pixel(column, row) {
+                    if Some(color) != color_key {
+                        self.set_pixel(x + column as i32, y + row as i32 * down, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Copy the logical pixels of a region into a sprite, the counterpart of `draw_sprite`
+    ///
+    /// The region has its top-left pixel at (x, y) and extends right and visually downward, so
+    /// drawing the result back at (x, y) restores it. The region is clipped to the grid and the
+    /// sprite only covers the part that was inside; when it is clipped at the left or top the
+    /// sprite's first pixel is the first one inside the grid rather than (x, y)
+    pub fn capture_region(&self, x: i32, y: i32, width: u32, height: u32) -> Sprite {
+        let (left, top) = self.to_unclipped_logical_coords(x, y);
+        let clip = |start: i32, length: u32, limit: u32| {
+            let start = start as i64;
+            (start.clamp(0, limit as i64) as u32, (start + length as i64).clamp(0, limit as i64) as u32)
+        };
+        let (x0, x1) = clip(left, width, self.logical_width);
+        let (y0, y1) = clip(top, height, self.logical_height);
+
+        let mut pixels = Vec::with_capacity(((x1 - x0) * (y1 - y0)) as usize);
+        for logical_y in y0..y1 {
+            for logical_x in x0..x1 {
+                pixels.push(self.get_logical_pixel(logical_x, logical_y).unwrap_or((0, 0, 0, 0)));
+            }
+        }
+        Sprite::new(x1 - x0, y1 - y0, pixels)
+    }
+
+    /// Replace the whole canvas with an image stretched over the logical pixel grid
+    ///
+    /// Each logical pixel takes the sprite pixel nearest to its center, so a sprite with exactly
+    /// the grid's dimensions is copied 1:1. When the dimensions differ the image is scaled
+    /// independently along each axis to cover the grid, which distorts it if the aspect ratios
+    /// don't match. Pixels are copied including alpha, without blending. An empty sprite leaves
+    /// the canvas untouched
+    pub fn draw_background_image(&mut self, sprite: &Sprite) {
+        self.modified = true;
+        if sprite.width() == 0 || sprite.height() == 0 {
+            return;
+        }
+        for logical_y in 0..self.logical_height {
+            let sprite_y = (2 * logical_y as u64 + 1) * sprite.height() as u64 / (2 * self.logical_height as u64);
+            for logical_x in 0..self.logical_width {
+                let sprite_x = (2 * logical_x as u64 + 1) * sprite.width() as u64 / (2 * self.logical_width as u64);
+                if let Some(color) = sprite.get_pixel(sprite_x as u32, sprite_y as u32) {
+                    self.set_logical_pixel(logical_x, logical_y, color);
+                }
+            }
+        }
+    }
+
+    /// Cover the whole canvas with copies of a sprite, one of which has its top-left pixel at `offset`
+    ///
+    /// The pattern wraps in both directions, so any offset works and shifting it a little every
+    /// frame scrolls the texture. Rows run visually downward as with `draw_sprite`, and pixels are
+    /// copied including alpha. An empty sprite leaves the canvas untouched
+    pub fn fill_pattern(&mut self, sprite: &Sprite, offset: (i32, i32)) {
+        self.modified = true;
+        if sprite.width() == 0 || sprite.height() == 0 {
+            return;
+        }
+        let down = self.down();
+        for logical_y in 0..self.logical_height {
+            for logical_x in 0..self.logical_width {
+                let (x, y) = self.to_user_coords(logical_x, logical_y);
+                let column = (x - offset.0).rem_euclid(sprite.width() as i32) as u32;
+                let row = ((y - offset.1) * down).rem_euclid(sprite.height() as i32) as u32;
+                if let Some(color) = sprite.get_pixel(column, row) {
+                    self.set_logical_pixel(logical_x, logical_y, color);
+                }
+            }
+        }
+    }
+
+    /// Draw a single character of the embedded 5×7 font with its top-left pixel at (x, y)
+    ///
+    /// Characters outside printable ASCII are drawn as '?'
+    pub fn draw_char(&mut self, x: i32, y: i32, c: char, color: Color) {
+        self.modified = true;
+        let down = self.down();
+        for column in 0..GLYPH_WIDTH {
+            for row in 0..GLYPH_HEIGHT {
+                if font::glyph_pixel(c, column, row) {
+                    self.set_pixel(x + column as i32, y + row as i32 * down, color);
+                }
+            }
+        }
+    }
+
+    /// Draw text with the embedded 5×7 font, with the top-left pixel of the first line at (x, y)
+    ///
+    /// Each `\n` starts a new line below the previous one. Text extends right and visually
+    /// downward in every coordinate system
+    pub fn draw_text(&mut self, x: i32, y: i32, text: &str, color: Color) {
+        self.modified = true;
+        let down = self.down();
+        for (line_index, line) in text.split('\n').enumerate() {
+            let line_y = y + (line_index as u32 * LINE_HEIGHT) as i32 * down;
+            for (char_index, c) in line.chars().enumerate() {
+                self.draw_char(x + (char_index as u32 * GLYPH_ADVANCE) as i32, line_y, c, color);
+            }
+        }
+    }
+
+    /// Draw text rotated by a quarter-turn multiple, with its bounding box's top-left pixel at (x, y)
+    ///
+    /// Glyphs are laid out exactly as by `draw_text` and the whole block is then turned, so the
+    /// result stays pixel-exact. The bounding box is `text_width` × `text_height` for `None` and
+    /// `Cw180`, and the transpose of that for `Cw90` and `Cw270`. (x, y) is that box's visual
+    /// top-left corner in every rotation and coordinate system
+    pub fn draw_text_rotated(&mut self, x: i32, y: i32, text: &str, color: Color, rotation: Rotation) {
+        self.modified = true;
+        let width = self.text_width(text) as i32;
+        let height = self.text_height(text) as i32;
+        let down = self.down();
+        for (line_index, line) in text.split('\n').enumerate() {
+            for (char_index, c) in line.chars().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    for row in 0..GLYPH_HEIGHT {
+                        if !font::glyph_pixel(c, column, row) {
+                            continue;
+                        }
+                        // Position within the upright block, then turned within the bounding box
+                        let u = (char_index as u32 * GLYPH_ADVANCE + column) as i32;
+                        let v = (line_index as u32 * LINE_HEIGHT + row) as i32;
+                        let (right, below) = match rotation {
+                            Rotation::None => (u, v),
+                            Rotation::Cw90 => (height - 1 - v, u),
+                            Rotation::Cw180 => (width - 1 - u, height - 1 - v),
+                            Rotation::Cw270 => (v, width - 1 - u),
+                        };
+                        self.set_pixel(x + right, y + below * down, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw a single line of text along a circle around (cx, cy), one rotated glyph at a time
+    ///
+    /// The text starts at `start_angle` (radians from +x towards +y, as in `fill_sector`) and
+    /// each glyph advances by its width measured along the circle. The glyphs' bottom edges sit
+    /// on the circle of `radius`: by default they face outward and the text runs clockwise on
+    /// screen, as on the upper half of a gauge; with `inward` they face the center and the text
+    /// runs counter-clockwise, reading upright along the bottom. Every glyph is turned to the
+    /// tangent at its middle and sampled nearest-neighbour, so 1-pixel strokes can thicken or
+    /// break at angles that aren't quarter turns; a radius of a few glyph heights or more keeps
+    /// them legible. Newlines are ignored
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_text_on_arc(
+        &mut self,
+        cx: i32,
+        cy: i32,
+        radius: u32,
+        start_angle: f32,
+        text: &str,
+        color: Color,
+        inward: bool,
+    ) {
+        self.modified = true;
+        if radius == 0 {
+            return;
+        }
+        let down = self.down();
+        let radius = radius as f32;
+        // Work in visual coordinates (y pointing down on screen), where growing angles turn
+        // clockwise; the text runs clockwise when facing outward
+        let direction = if inward { -1.0 } else { 1.0 };
+        let start = if down > 0 { start_angle } else { -start_angle };
+        let (half_width, height) = (GLYPH_WIDTH as f32 / 2.0, GLYPH_HEIGHT as f32);
+        let reach = (half_width * half_width + height * height).sqrt().ceil() as i32 + 1;
+
+        for (index, c) in text.chars().filter(|&c| c != '\n').enumerate() {
+            let along = index as f32 * GLYPH_ADVANCE as f32 + half_width;
+            let (sin, cos) = (start + direction * along / radius).sin_cos();
+            // Bottom middle of the glyph, and its up and right directions
+            let (base_x, base_y) = (cos * radius, sin * radius);
+            let up = if inward { (-cos, -sin) } else { (cos, sin) };
+            let right = (-up.1, up.0);
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let (px, py) = (base_x.round() + dx as f32, base_y.round() + dy as f32);
+                    let (offset_x, offset_y) = (px - base_x, py - base_y);
+                    let column = offset_x * right.0 + offset_y * right.1 + half_width;
+                    let row = height - (offset_x * up.0 + offset_y * up.1);
+                    if column < 0.0 || row < 0.0 {
+                        continue;
+                    }
+                    if font::glyph_pixel(c, column as u32, row as u32) {
+                        self.set_pixel(cx + px as i32, cy + py as i32 * down, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Width in logical pixels of the widest line of `text` as drawn by `draw_text`
+    pub fn text_width(&self, text: &str) -> u32 {
+        text.split('\n').map(font::line_width).max().unwrap_or(0)
+    }
+
+    /// Height in logical pixels of `text` as drawn by `draw_text`
+    ///
+    /// Every line counts, including empty ones (so a trailing `\n` adds a line)
+    pub fn text_height(&self, text: &str) -> u32 {
+        let lines = text.split('\n').count() as u32;
+        lines * LINE_HEIGHT - 1
+    }
+
+    /// Draw text like `draw_text` with every font pixel enlarged to a `scale`×`scale` block
+    ///
+    /// Glyph spacing and line height grow by the same factor. A scale of 0 draws nothing
+    pub fn draw_text_scaled(&mut self, x: i32, y: i32, text: &str, color: Color, scale: u32) {
+        self.modified = true;
+        let down = self.down();
+        let scale = scale as i32;
+        for (line_index, line) in text.split('\n').enumerate() {
+            let line_top = (line_index as u32 * LINE_HEIGHT) as i32 * scale;
+            for (char_index, c) in line.chars().enumerate() {
+                let char_left = (char_index as u32 * GLYPH_ADVANCE) as i32 * scale;
+                for column in 0..GLYPH_WIDTH {
+                    for row in 0..GLYPH_HEIGHT {
+                        if !font::glyph_pixel(c, column, row) {
+                            continue;
+                        }
+                        let block_x = x + char_left + column as i32 * scale;
+                        let block_y = line_top + row as i32 * scale;
+                        self.fill_visual_rect(block_x, y + block_y * down, scale as u32, scale as u32, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw text as a chunky LED-matrix banner, each font pixel a separate `block_size` square
+    ///
+    /// Laid out like `draw_text_scaled` with a scale of `block_size + 1`, but every block leaves
+    /// a 1-pixel gap on its right and below it, so neighbouring lit pixels stay visibly apart.
+    /// (x, y) is the visual top-left of the first block. A block size of 0 draws nothing
+    pub fn draw_banner(&mut self, x: i32, y: i32, text: &str, block_size: u32, color: Color) {
+        self.modified = true;
+        if block_size == 0 {
+            return;
+        }
+        let down = self.down();
+        let pitch = block_size as i32 + 1;
+        for (line_index, line) in text.split('\n').enumerate() {
+            let line_top = (line_index as u32 * LINE_HEIGHT) as i32 * pitch;
+            for (char_index, c) in line.chars().enumerate() {
+                let char_left = (char_index as u32 * GLYPH_ADVANCE) as i32 * pitch;
+                for column in 0..GLYPH_WIDTH {
+                    for row in 0..GLYPH_HEIGHT {
+                        if !font::glyph_pixel(c, column, row) {
+                            continue;
+                        }
+                        let block_x = x + char_left + column as i32 * pitch;
+                        let block_y = line_top + row as i32 * pitch;
+                        self.fill_visual_rect(block_x, y + block_y * down, block_size, block_size, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Bounding box (width, height) of `text` drawn with `draw_text_scaled` at `scale`
+    ///
+    /// The width is that of the widest line and the height covers every line, including empty
+    /// ones such as after a trailing `\n`, without spacing below the last. At scale 1 this is
+    /// `(text_width, text_height)`
+    pub fn measure_text(&self, text: &str, scale: u32) -> (u32, u32) {
+        (self.text_width(text) * scale, self.text_height(text) * scale)
+    }
+
+    /// Draw text on top of a background box
+    ///
+    /// The box's top-left pixel is at (x, y) and it extends `padding` pixels beyond the text on
+    /// every side, sized to the widest line and total height of the text. The background is
+    /// alpha-blended, so a translucent `bg_color` lets the content underneath show through.
+    pub fn draw_text_boxed(
+        &mut self,
+        x: i32,
+        y: i32,
+        text: &str,
+        text_color: Color,
+        bg_color: Color,
+        padding: u32,
+    ) {
+        self.modified = true;
+        let width = self.text_width(text) + 2 * padding;
+        let height = self.text_height(text) + 2 * padding;
+        let down = self.down();
+        for row in 0..height as i32 {
+            for column in 0..width as i32 {
+                self.blend_pixel(x + column, y + row * down, bg_color);
+            }
+        }
+        self.draw_text(x + padding as i32, y + padding as i32 * down, text, text_color);
+    }
+
+    /// The y step in user coordinates that moves one pixel visually downward
+    fn down(&self) -> i32 {
+        match self.coordinate_system {
+            CoordinateSystem::TopLeft => 1,
+            CoordinateSystem::Center => -1,
+        }
+    }
+
+    /// Flood fill the 4-connected region of exactly the seed pixel's color, starting at (x, y)
+    ///
+    /// Equivalent to `flood_fill_tolerant` with a tolerance of 0
+    pub fn flood_fill(&mut self, x: i32, y: i32, fill_color: Color) {
+        self.modified = true;
+        self.flood_fill_tolerant(x, y, fill_color, 0);
+    }
+
+    /// Flood fill the 4-connected region of colors similar to the seed pixel's color, starting at (x, y)
+    ///
+    /// A pixel matches when every channel (R, G, B and A) differs from the seed color by at most
+    /// `tolerance`, i.e. the Chebyshev distance between the two colors is `<= tolerance`.
+    /// Distances are always measured against the original seed color, not the neighbouring pixel,
+    /// so gradients do not "leak" further than the tolerance allows.
+    ///
+    /// Does nothing if the seed is out of bounds
+    pub fn flood_fill_tolerant(&mut self, x: i32, y: i32, fill_color: Color, tolerance: u8) {
+        self.modified = true;
+        let Some((seed_x, seed_y)) = self.to_logical_coords(x, y) else {
+            return;
+        };
+        let Some(seed_color) = self.get_logical_pixel(seed_x, seed_y) else {
+            return;
+        };
+
+        self.flood_fill_logical(seed_x, seed_y, fill_color, |color| {
+            color_distance(color, seed_color) <= tolerance
+        });
+    }
+
+    /// Boundary fill: fill the 4-connected region around (x, y) up to pixels of `border_color`
+    ///
+    /// Unlike `flood_fill`, the interior does not need to be a single color; every pixel that is
+    /// not exactly `border_color` is filled. The region must be fully enclosed by the border,
+    /// otherwise the fill spreads to the canvas edges.
+    ///
+    /// Does nothing if the seed is out of bounds or is itself a border pixel
+    pub fn flood_fill_to_border(
+        &mut self,
+        x: i32,
+        y: i32,
+        fill_color: Color,
+        border_color: Color,
+    ) {
+        self.modified = true;
+        let Some((seed_x, seed_y)) = self.to_logical_coords(x, y) else {
+            return;
+        };
+
+        self.flood_fill_logical(seed_x, seed_y, fill_color, |color| color != border_color);
+    }
+
+    /// Stack-based 4-connected fill over logical pixels (in buffer coordinates)
+    ///
+    /// `matches` decides whether a pixel belongs to the region; each pixel is visited at most once
+    fn flood_fill_logical(
+        &mut self,
+        seed_x: u32,
+        seed_y: u32,
+        fill_color: Color,
+        matches: impl Fn(Color) -> bool,
+    ) {
+        let width = self.logical_width;
+        let height = self.logical_height;
+        let mut visited = vec![false; (width * height) as usize];
+        let mut stack = vec![(seed_x, seed_y)];
+
+        while let Some((logical_x, logical_y)) = stack.pop() {
+            let idx = (logical_y * width + logical_x) as usize;
+            if visited[idx] {
+                continue;
+            }
+            visited[idx] = true;
+
+            match self.get_logical_pixel(logical_x, logical_y) {
+                Some(color) if matches(color) => {}
+                _ => continue,
+            }
+            self.set_logical_pixel(logical_x, logical_y, fill_color);
+
+            if logical_x > 0 {
+                stack.push((logical_x - 1, logical_y));
+            }
+            if logical_x + 1 < width {
+                stack.push((logical_x + 1, logical_y));
+            }
+            if logical_y > 0 {
+                stack.push((logical_x, logical_y - 1));
+            }
+            if logical_y + 1 < height {
+                stack.push((logical_x, logical_y + 1));
+            }
+        }
+    }
+
+    /// Stroke a polyline with an anti-aliased line of the given width
+    ///
+    /// Points are in user coordinates, with integer values at pixel centers. The outline of the
+    /// stroke is built from one quad per segment plus the requested joins and caps, and each pixel
+    /// is blended with `color` weighted by how much of it the outline covers (estimated with a
+    /// 4×4 grid of samples per logical pixel).
+    ///
+    /// If the first and last points are equal the path is treated as closed, so it is joined at
+    /// that point and `cap` is ignored
+    pub fn stroke_polyline(
+        &mut self,
+        points: &[(f32, f32)],
+        width: f32,
+        color: Color,
+        join: LineJoin,
+        cap: LineCap,
+    ) {
+        self.modified = true;
+        let pieces = stroke_pieces(points, width, join, cap);
+        self.fill_pieces_aa(&pieces, color);
+    }
+
+    /// Fill the union of convex pieces with anti-aliasing
+    fn fill_pieces_aa(&mut self, pieces: &[Piece], color: Color) {
+        const SAMPLES: u32 = 4;
+
+        if pieces.is_empty() {
+            return;
+        }
+        let Some((canvas_min_x, canvas_min_y, canvas_max_x, canvas_max_y)) = self.user_bounds() else {
+            return;
+        };
+        let (min_x, min_y, max_x, max_y) = pieces.iter().map(Piece::bounds).fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |acc, b| (acc.0.min(b.0), acc.1.min(b.1), acc.2.max(b.2), acc.3.max(b.3)),
+        );
+        // Only visit pixels that are both in the bounding box and on the canvas
+        let clip = |min: f32, max: f32, canvas_min: i32, canvas_max: i32| {
+            (min.round().max(canvas_min as f32) as i32)..=(max.round().min(canvas_max as f32) as i32)
+        };
+
+        for y in clip(min_y, max_y, canvas_min_y, canvas_max_y) {
+            for x in clip(min_x, max_x, canvas_min_x, canvas_max_x) {
+                let mut covered = 0;
+                for sy in 0..SAMPLES {
+                    for sx in 0..SAMPLES {
+                        let sample = (
+                            x as f32 - 0.5 + (sx as f32 + 0.5) / SAMPLES as f32,
+                            y as f32 - 0.5 + (sy as f32 + 0.5) / SAMPLES as f32,
+                        );
+                        if pieces.iter().any(|piece| piece.contains(sample)) {
+                            covered += 1;
+                        }
+                    }
+                }
+                if covered > 0 {
+                    let alpha = color.3 as u32 * covered / (SAMPLES * SAMPLES);
+                    self.blend_pixel(x, y, (color.0, color.1, color.2, alpha as u8));
+                }
+            }
+        }
+    }
+
+    /// Write a copy of this canvas resampled with bilinear filtering between logical pixel centers
+    ///
+    /// Each logical pixel is normally expanded into a solid block of physical pixels; the copy
+    /// replaces the blocks with a smooth interpolation of neighbouring logical colors, which
+    /// suits continuous fields but blurs hard edges. Alpha is interpolated too. `output` must be
+    /// laid out like the frame buffer this canvas draws into (same length and row width), and
+    /// only this canvas's region of it is written; physical pixels past the logical grid are
+    /// copied unchanged. The canvas itself is left untouched, so smoothing a persistent buffer
+    /// every frame never compounds the blur.
+    ///
+    /// `App` does this when `WorldConfig::smooth_upscale` is set: the world draws into a
+    /// separate buffer that keeps the unsmoothed picture between frames, its smoothed copy goes
+    /// into the window's buffer, and widgets and grid lines are drawn on top of that, so they
+    /// stay crisp. Panics if `output` has a different length from the frame buffer
+    pub fn smooth_upscale_into(&self, output: &mut [u8]) {
+        assert_eq!(output.len(), self.frame.len(), "output must match the frame buffer");
+        for phys_y in 0..self.physical_height {
+            let start = self.pixel_index(0, phys_y);
+            let end = start + self.physical_width as usize * 4;
+            output[start..end].copy_from_slice(&self.frame[start..end]);
+        }
+        if self.logical_width == 0 || self.logical_height == 0 {
+            return;
+        }
+
+        let mut colors = Vec::with_capacity((self.logical_width * self.logical_height) as usize);
+        for logical_y in 0..self.logical_height {
+            for logical_x in 0..self.logical_width {
+                colors.push(self.get_logical_pixel(logical_x, logical_y).unwrap_or_default());
+            }
+        }
+        let width = self.logical_width;
+        let height = self.logical_height;
+        let sample = |x: u32, y: u32| colors[(y * width + x) as usize];
+
+        for phys_y in 0..(height * self.pixel_scale_y).min(self.physical_height) {
+            let v = ((phys_y as f32 + 0.5) / self.pixel_scale_y as f32 - 0.5).max(0.0);
+            let y0 = (v.floor() as u32).min(height - 1);
+            let y1 = (y0 + 1).min(height - 1);
+            let ty = v - y0 as f32;
+            for phys_x in 0..(width * self.pixel_scale_x).min(self.physical_width) {
+                let u = ((phys_x as f32 + 0.5) / self.pixel_scale_x as f32 - 0.5).max(0.0);
+                let x0 = (u.floor() as u32).min(width - 1);
+                let x1 = (x0 + 1).min(width - 1);
+                let tx = u - x0 as f32;
+
+                let top = lerp_color(sample(x0, y0), sample(x1, y0), tx);
+                let bottom = lerp_color(sample(x0, y1), sample(x1, y1), tx);
+                let color = lerp_color(top, bottom, ty);
+                let idx = self.pixel_index(phys_x, phys_y);
+                output[idx..idx + 4].copy_from_slice(&[color.0, color.1, color.2, color.3]);
+            }
+        }
+    }
+
+    /// Register a named viewport for use with `in_viewport`
+    ///
+    /// Viewports live as long as the canvas, so register them at the start of `World::draw`.
+    /// Registering a name again replaces the previous viewport
+    pub fn add_viewport(&mut self, name: impl Into<String>, viewport: Viewport) {
+        self.viewports.insert(name.into(), viewport);
+    }
+
+    /// Draw into a registered viewport
+    ///
+    /// `f` receives a canvas restricted to the viewport's region, using its coordinate system and
+    /// scale. All drawing inside is clipped to the region.
+    ///
+    /// Returns false (without calling `f`) if no viewport with that name is registered
+    pub fn in_viewport(&mut self, name: &str, f: impl FnOnce(&mut Canvas)) -> bool {
+        let Some(viewport) = self.viewports.get(name).copied() else {
+            return false;
+        };
+        // Clip the region to this canvas
+        let x = viewport.x.min(self.logical_width);
+        let y = viewport.y.min(self.logical_height);
+        let width = viewport.width.min(self.logical_width - x);
+        let height = viewport.height.min(self.logical_height - y);
+
+        let mut sub_canvas = self.sub_canvas(
+            x * self.pixel_scale_x,
+            y * self.pixel_scale_y,
+            width * self.pixel_scale_x,
+            height * self.pixel_scale_y,
+            width / viewport.scale.max(1),
+            height / viewport.scale.max(1),
+            viewport.coordinate_system,
+        );
+        f(&mut sub_canvas);
+        self.modified |= sub_canvas.modified;
+        true
+    }
+
+    /// Draw into a region of this canvas at a finer logical resolution, as a detail inset
+    ///
+    /// `rect` covers the same pixels as `fill_rect` would, and within it every logical pixel of
+    /// this canvas is split into `subdivision`×`subdivision` pixels of the inset canvas passed to
+    /// `f`. The inset has its own `coordinate_system`; with `TopLeft`, inset pixel (ix, iy) lies
+    /// inside the main logical pixel (ix / subdivision, iy / subdivision) counted from the
+    /// region's top-left pixel. Drawing is clipped to the region, and a `rect` partly outside
+    /// the canvas is clipped first.
+    ///
+    /// A logical pixel cannot be split finer than the physical pixels it covers, so the
+    /// subdivision is limited to the pixel scale; pick a divisor of the scale for an exact fit,
+    /// as other values leave the inset slightly smaller than the region
+    pub fn in_inset(
+        &mut self,
+        rect: Rect,
+        subdivision: u32,
+        coordinate_system: CoordinateSystem,
+        f: impl FnOnce(&mut Canvas),
+    ) {
+        // The far corner in i64, since it can lie past i32::MAX for a rect near the edge
+        let far_x = rect.x as i64 + rect.width as i64 - 1;
+        let far_y = rect.y as i64 + rect.height as i64 - 1;
+        let (x0, y0) = self.to_unclipped_logical_coords(rect.x, rect.y);
+        let (x1, y1) = self.to_unclipped_logical_coords(
+            far_x.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+            far_y.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        );
+        let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+        let clip_x = |x: i64| x.clamp(0, self.logical_width as i64) as u32;
+        let clip_y = |y: i64| y.clamp(0, self.logical_height as i64) as u32;
+        let (left, right) = (clip_x(x0.min(x1)), clip_x(x0.max(x1) + 1));
+        let (top, bottom) = (clip_y(y0.min(y1)), clip_y(y0.max(y1) + 1));
+        let (width, height) = if rect.width == 0 || rect.height == 0 {
+            (0, 0)
+        } else {
+            (right - left, bottom - top)
+        };
+
+        let subdivision = subdivision.max(1);
+        let mut inset = self.sub_canvas(
+            left * self.pixel_scale_x,
+            top * self.pixel_scale_y,
+            width * self.pixel_scale_x,
+            height * self.pixel_scale_y,
+            width * subdivision,
+            height * subdivision,
+            coordinate_system,
+        );
+        f(&mut inset);
+        self.modified |= inset.modified;
+    }
+
+    /// Create a canvas over a physical region of this canvas with its own logical resolution
+    ///
+    /// The region must lie within this canvas. Degenerate regions produce a canvas that ignores
+    /// all drawing
+    #[allow(clippy::too_many_arguments)]
+    fn sub_canvas(
+        &mut self,
+        phys_x: u32,
+        phys_y: u32,
+        physical_width: u32,
+        physical_height: u32,
+        logical_width: u32,
+        logical_height: u32,
+        coordinate_system: CoordinateSystem,
+    ) -> Canvas<'_> {
+        let (logical_width, logical_height) = if logical_width == 0 || logical_height == 0 {
+            (0, 0)
+        } else {
+            (logical_width.min(physical_width), logical_height.min(physical_height))
+        };
+        let pixel_scale_x = physical_width.checked_div(logical_width).unwrap_or(0);
+        let pixel_scale_y = physical_height.checked_div(logical_height).unwrap_or(0);
+
+        Canvas {
+            frame: &mut *self.frame,
+            stride: self.stride,
+            origin_x: self.origin_x + phys_x,
+            origin_y: self.origin_y + phys_y,
+            physical_width,
+            physical_height,
+            logical_width,
+            logical_height,
+            pixel_scale_x,
+            pixel_scale_y,
+            coordinate_system,
+            show_grid: false,
+            grid_color: self.grid_color,
+            grid_style: self.grid_style,
+            viewports: HashMap::new(),
+            modified: false,
+            #[cfg(feature = "overdraw")]
+            overdraw: vec![0; (logical_width * logical_height) as usize],
+            ids: Vec::new(),
+        }
+    }
+
+    /// Draw grid lines between logical pixels (internal method, called automatically if show_grid is true)
+    ///
+    /// With `GridStyle::Dots` only the crossings of those lines are plotted
+    pub(crate) fn draw_grid(&mut self) {
+        if !self.show_grid || self.pixel_scale_x <= 1 || self.pixel_scale_y <= 1 {
+            return;
+        }
+
+        if self.grid_style == GridStyle::Dots {
+            // The same columns and rows the lines below use, including the far edges
+            let columns: Vec<u32> = (0..self.logical_width)
+                .map(|logical_x| logical_x * self.pixel_scale_x)
+                .chain([self.physical_width - 1])
+                .collect();
+            let rows: Vec<u32> = (0..self.logical_height)
+                .map(|logical_y| logical_y * self.pixel_scale_y)
+                .chain([self.physical_height - 1])
+                .collect();
+            for &phys_y in &rows {
+                for &phys_x in &columns {
+                    self.set_physical_pixel(phys_x, phys_y, self.grid_color);
+                }
+            }
+            return;
+        }
+
+        // Draw vertical lines (including boundaries)
+        for logical_x in 0..self.logical_width {
+            let phys_x = logical_x * self.pixel_scale_x;
+            for phys_y in 0..self.physical_height {
+                self.set_physical_pixel(phys_x, phys_y, self.grid_color);
+            }
+        }
+        // Draw right edge
+        for phys_y in 0..self.physical_height {
+            self.set_physical_pixel(self.physical_width - 1, phys_y, self.grid_color);
+        }
+
+        // Draw horizontal lines (including boundaries)
+        for logical_y in 0..self.logical_height {
+            let phys_y = logical_y * self.pixel_scale_y;
+            for phys_x in 0..self.physical_width {
+                self.set_physical_pixel(phys_x, phys_y, self.grid_color);
+            }
+        }
+        // Draw bottom edge
+        for phys_x in 0..self.physical_width {
+            self.set_physical_pixel(phys_x, self.physical_height - 1, self.grid_color);
+        }
+    }
+}
+
+/// Size in physical pixels (horizontal, vertical) of one logical pixel
+///
+/// `pixel_aspect` is the desired width:height ratio of a logical pixel. At exactly 1.0 each axis
+/// is scaled independently by `physical / logical`, so the grid fills the buffer even when its
+/// shape differs (a 40×60 grid on 320×240 gets 8×4 blocks). Otherwise the vertical scale is the
+/// largest that fits both axes once the horizontal scale is derived from it, so with a 320×240
+/// buffer a 2.0 aspect on an 80×120 grid gives 4×2 blocks; the horizontal scale never exceeds
+/// what fits, so extreme aspects give the widest block that does. An aspect that is not a
+/// positive, finite number counts as 1.0. Scales are never smaller than 1, which is also what a
+/// grid with no columns or rows gets.
+pub fn pixel_scales(
+    physical_width: u32,
+    physical_height: u32,
+    logical_width: u32,
+    logical_height: u32,
+    pixel_aspect: f32,
+) -> (u32, u32) {
+    if logical_width == 0 || logical_height == 0 {
+        return (1, 1);
+    }
+    let scale_x = physical_width / logical_width;
+    let scale_y = physical_height / logical_height;
+    let pixel_aspect = if pixel_aspect.is_finite() && pixel_aspect > 0.0 { pixel_aspect } else { 1.0 };
+    if pixel_aspect == 1.0 {
+        return (scale_x.max(1), scale_y.max(1));
+    }
+
+    let fitted_y = (scale_y as f32).min(scale_x as f32 / pixel_aspect).floor().max(1.0);
+    let fitted_x = (fitted_y * pixel_aspect).round().clamp(1.0, scale_x.max(1) as f32);
+    (fitted_x as u32, fitted_y as u32)
+}
+
+/// Perceived brightness of a color using the Rec. 601 luma weights (alpha is ignored)
+pub fn luminance(color: Color) -> u8 {
+    (0.299 * color.0 as f32 + 0.587 * color.1 as f32 + 0.114 * color.2 as f32).round() as u8
+}
+
+/// Whether the pixel (px, py) lies in the circle of `radius` around (cx, cy)
+///
+/// Inside means dx² + dy² ≤ radius² + radius, so the pixels at exactly `radius` along the axes
+/// are in and the next ones out. These are exactly the pixels `Canvas::fill_circle` fills, so
+/// hit-testing a drawn circle matches what is on screen
+pub fn point_in_circle(px: i32, py: i32, cx: i32, cy: i32, radius: u32) -> bool {
+    // Wide enough for the squares of any two i32 coordinates' difference
+    let (dx, dy) = (px as i128 - cx as i128, py as i128 - cy as i128);
+    let radius = radius as i128;
+    dx * dx + dy * dy <= radius * radius + radius
+}
+
+/// Every pixel on the outline of the rectangle `Canvas::fill_rect` would fill, each exactly once
+///
+/// Starts at (x, y) and runs along the x axis first, continuing around the rectangle
+fn rect_outline(x: i32, y: i32, width: u32, height: u32) -> impl Iterator<Item = (i32, i32)> {
+    let (w, h) = (width as i32, height as i32);
+    let top = (0..w).map(move |dx| (x + dx, y));
+    let right = (1..h).map(move |dy| (x + w - 1, y + dy)).filter(move |_| w > 0);
+    let bottom = (0..w - 1).rev().map(move |dx| (x + dx, y + h - 1)).filter(move |_| h > 1);
+    let left = (1..h - 1).rev().map(move |dy| (x, y + dy)).filter(move |_| w > 1);
+    top.chain(right).chain(bottom).chain(left)
+}
+
+/// The pixels `Canvas::draw_rect_dashed` draws, in order around the outline from (x, y)
+fn dashed_outline(
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    dash_len: u32,
+    gap_len: u32,
+    phase: u32,
+) -> impl Iterator<Item = (i32, i32)> {
+    let period = (dash_len + gap_len).max(1);
+    let shift = period - phase % period;
+    rect_outline(x, y, width, height)
+        .enumerate()
+        .filter(move |&(i, _)| width > 0 && height > 0 && (i as u32 + shift) % period < dash_len)
+        .map(|(_, point)| point)
+}
+
+/// Radius of ring `i` of `count` out to `max_radius`, computed in u64 so the product can't overflow
+fn ring_radius(max_radius: u32, i: u32, count: u32) -> u32 {
+    (max_radius as u64 * i as u64 / count as u64) as u32
+}
+
+/// Half the width of row `row` of the circle of `radius`, None for rows past its rim
+///
+/// This is the largest x with x² + row² ≤ radius² + radius, the rim of what `point_in_circle`
+/// counts as inside. Within an octant it drops by at most 1 per row, so the rim pixels mirrored
+/// across the axes and diagonals form a gapless outline. Up to radius 52 the outline matches
+/// the midpoint circle algorithm
+fn circle_half_width(radius: u32, row: u64) -> Option<u64> {
+    let limit = radius as u64 * radius as u64 + radius as u64;
+    limit.checked_sub(row.checked_mul(row)?).map(u64::isqrt)
+}
+
+/// The marching-squares segments where a `cols`×`rows` grid of samples crosses `level`
+///
+/// Endpoints are in grid units: (col, row) is the sample at that index, with rows counting down.
+/// See `Canvas::draw_contour` for how samples are classified and saddles resolved
+fn contour_segments(cols: u32, rows: u32, values: &[f32], level: f32) -> Vec<((f32, f32), (f32, f32))> {
+    let mut segments = Vec::new();
+    for row in 0..rows.saturating_sub(1) {
+        for col in 0..cols.saturating_sub(1) {
+            let sample = |c: u32, r: u32| values[(r * cols + c) as usize];
+            // Corners clockwise from the top-left, as (grid position, value)
+            let corners = [
+                ((col as f32, row as f32), sample(col, row)),
+                (((col + 1) as f32, row as f32), sample(col + 1, row)),
+                (((col + 1) as f32, (row + 1) as f32), sample(col + 1, row + 1)),
+                ((col as f32, (row + 1) as f32), sample(col, row + 1)),
+            ];
+            // Crossing points on the top, right, bottom and left edges, in that order
+            let mut crossings = Vec::with_capacity(4);
+            for i in 0..4 {
+                let ((ax, ay), a) = corners[i];
+                let ((bx, by), b) = corners[(i + 1) % 4];
+                if (a >= level) != (b >= level) {
+                    let t = (level - a) / (b - a);
+                    crossings.push((ax + (bx - ax) * t, ay + (by - ay) * t));
+                }
+            }
+            match crossings[..] {
+                [from, to] => segments.push((from, to)),
+                [top, right, bottom, left] => {
+                    let center = corners.iter().map(|&(_, value)| value).sum::<f32>() / 4.0;
+                    if (center >= level) == (corners[0].1 >= level) {
+                        // The top-left corner connects through the middle, cut off the other two
+                        segments.push((top, right));
+                        segments.push((bottom, left));
+                    } else {
+                        segments.push((left, top));
+                        segments.push((right, bottom));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    segments
+}
+
+/// The field inside one marching-squares cell at (u, v), both from 0 to 1 with v counting down
+///
+/// `corners` are clockwise from the top-left. The cell is split into four triangles meeting at
+/// its center, which takes the corners' average, and the value is linear within each triangle
+fn cell_value(corners: [f32; 4], u: f32, v: f32) -> f32 {
+    let center = corners.iter().sum::<f32>() / 4.0;
+    // The triangle is the one whose cell edge is nearest, with t running along that edge
+    // (clockwise) and s the distance from the edge towards the center, both from 0 to 1
+    let (edge, t, s) = [(0, u, v), (1, v, 1.0 - u), (2, 1.0 - u, 1.0 - v), (3, 1.0 - v, u)]
+        .into_iter()
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .unwrap_or((0, u, v));
+    let (a, b) = (corners[edge], corners[(edge + 1) % 4]);
+    if s >= 0.5 {
+        return center;
+    }
+    // Barycentric weights of the two edge corners and the center
+    let along = (t - 0.5) / (1.0 - 2.0 * s) + 0.5;
+    let edge_weight = 1.0 - 2.0 * s;
+    edge_weight * (a + (b - a) * along) + (1.0 - edge_weight) * center
+}
+
+/// Length of the dashes and of the gaps between them in `draw_safe_area`
+const SAFE_AREA_DASH: u32 = 4;
+
+/// Id buffer value of a pixel that is not tagged with `set_pixel_id`
+pub(crate) const NO_ID: u32 = u32::MAX;
+
+/// Most line segments `Canvas::draw_spiral`, `Canvas::draw_arc` and each piece of
+/// `Canvas::draw_spline` join, however long the curve
+const MAX_CURVE_STEPS: u32 = 1 << 20;
+
+/// Length in pixels of the arrowhead strokes drawn by `Canvas::draw_vector_field`
+const FIELD_ARROWHEAD_SIZE: u32 = 3;
+
+/// A tick value as legend text: whole numbers without decimals, others with up to two
+fn format_tick_label(value: f32) -> String {
+    let text = format!("{value:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    // Rounding can leave a negative zero, as for -0.001
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Positions within `min..=max` that are non-zero multiples of `spacing` (at least 1) away from
+/// `origin`
+///
+/// Computed in i64, so neither the distances nor the positions overflow for any i32 inputs
+fn axis_ticks(origin: i32, min: i32, max: i32, spacing: u32) -> impl Iterator<Item = i32> {
+    let (origin, spacing) = (origin as i64, spacing as i64);
+    // The first multiple at or after `min` and the last at or before `max`
+    let first = (min as i64 - origin + spacing - 1).div_euclid(spacing);
+    let last = (max as i64 - origin).div_euclid(spacing);
+    (first..=last).filter(|&k| k != 0).map(move |k| (origin + k * spacing) as i32)
+}
+
+/// Linearly interpolate between two colors, channel by channel (t = 0 gives a, t = 1 gives b)
+pub(crate) fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    (lerp(a.0, b.0), lerp(a.1, b.1), lerp(a.2, b.2), lerp(a.3, b.3))
+}
+
+/// Largest per-channel difference between two colors
+fn color_distance(a: Color, b: Color) -> u8 {
+    a.0.abs_diff(b.0)
+        .max(a.1.abs_diff(b.1))
+        .max(a.2.abs_diff(b.2))
+        .max(a.3.abs_diff(b.3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A top-left canvas over `frame` with no grid
+    fn new_canvas(frame: &mut [u8], physical: (u32, u32), logical: (u32, u32)) -> Canvas<'_> {
+        Canvas::new(frame, physical.0, physical.1, logical.0, logical.1, CoordinateSystem::TopLeft, false, (0, 0, 0, 0))
+    }
+
+    #[test]
+    fn smooth_upscale_into_does_not_compound() {
+        let mut frame = vec![0; 8 * 4 * 4];
+        let mut output = vec![0; frame.len()];
+        let mut again = vec![0; frame.len()];
+        let mut canvas = new_canvas(&mut frame, (8, 4), (2, 1));
+        canvas.set_pixel(0, 0, (0, 0, 0, 255));
+        canvas.set_pixel(1, 0, (255, 255, 255, 255));
+        canvas.smooth_upscale_into(&mut output);
+        canvas.smooth_upscale_into(&mut again);
+
+        assert_eq!(output, again);
+        assert_eq!(canvas.get_pixel(0, 0), Some((0, 0, 0, 255)));
+        assert_eq!(canvas.get_pixel(1, 0), Some((255, 255, 255, 255)));
+        let red_at = |x: usize| output[x * 4];
+        assert_eq!(red_at(0), 0);
+        assert!(red_at(3) > 0 && red_at(3) < red_at(4) && red_at(4) < 255);
+        assert_eq!(red_at(7), 255);
+    }
+
+    #[test]
+    fn off_canvas_drawing_counts_as_modified() {
+        let mut frame = vec![0; 4 * 4 * 4];
+        let mut pixel = new_canvas(&mut frame, (4, 4), (4, 4));
+        assert!(!pixel.was_modified());
+        assert!(!pixel.set_pixel(-1, 10, (255, 0, 0, 255)));
+        assert!(pixel.was_modified());
+
+        let mut rect = new_canvas(&mut frame, (4, 4), (4, 4));
+        rect.fill_rect(10, 10, 2, 2, (255, 0, 0, 255));
+        assert!(rect.was_modified());
+    }
+
+    #[test]
+    fn draw_spiral_with_huge_turns_finishes() {
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        canvas.draw_spiral(8, 8, 1.0e9, 7.0, SpiralKind::Archimedean, (255, 255, 255, 255));
+        canvas.draw_spiral(8, 8, f32::INFINITY, 7.0, SpiralKind::Logarithmic, (255, 255, 255, 255));
+        assert_eq!(canvas.get_pixel(8, 8), Some((255, 255, 255, 255)));
+    }
+
+    #[test]
+    fn point_in_circle_matches_fill_circle() {
+        for radius in [0, 1, 5, 52, 53, 90] {
+            let size = radius * 2 + 3;
+            let mut frame = vec![0; (size * size * 4) as usize];
+            let mut canvas = new_canvas(&mut frame, (size, size), (size, size));
+            let center = radius as i32 + 1;
+            canvas.fill_circle(center, center, radius, (255, 255, 255, 255));
+            for y in 0..size as i32 {
+                for x in 0..size as i32 {
+                    let filled = canvas.get_pixel(x, y) == Some((255, 255, 255, 255));
+                    assert_eq!(filled, point_in_circle(x, y, center, center, radius), "radius {radius} at ({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn point_in_circle_at_the_rim() {
+        // 10² + 0² ≤ 110 is in, 11² is out; 7² + 7² = 98 is in, 8² + 7² = 113 is out
+        assert!(point_in_circle(10, 0, 0, 0, 10));
+        assert!(!point_in_circle(11, 0, 0, 0, 10));
+        assert!(point_in_circle(-7, 7, 0, 0, 10));
+        assert!(!point_in_circle(-8, 7, 0, 0, 10));
+        // Far-apart coordinates don't overflow
+        assert!(!point_in_circle(i32::MAX, i32::MIN, i32::MIN, i32::MAX, 10));
+        assert!(point_in_circle(i32::MIN, 0, i32::MIN, 0, 0));
+    }
+
+    /// The pixels Bresenham's algorithm plots from (x0, y0) to (x1, y1), without clipping
+    fn bresenham(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        let mut points = vec![(x, y)];
+        while (x, y) != (x1, y1) {
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+            points.push((x, y));
+        }
+        points
+    }
+
+    /// User coordinates of every pixel on `canvas` with the given color
+    fn pixels_of(canvas: &Canvas, color: Color) -> Vec<(i32, i32)> {
+        let mut pixels = Vec::new();
+        canvas.for_each_pixel(|x, y, pixel| {
+            if pixel == color {
+                pixels.push((x, y));
+            }
+        });
+        pixels.sort();
+        pixels
+    }
+
+    #[test]
+    fn clipped_draw_line_plots_the_bresenham_pixels() {
+        let white = (255, 255, 255, 255);
+        let ends = [-9, -3, 0, 2, 5, 7, 11, 20];
+        for coordinate_system in [CoordinateSystem::TopLeft, CoordinateSystem::Center] {
+            let points: Vec<_> = ends.iter().flat_map(|&x| ends.map(|y| (x, y))).collect();
+            for ((x0, y0), (x1, y1)) in points.iter().flat_map(|&start| points.iter().map(move |&end| (start, end))) {
+                let mut frame = vec![0; 8 * 8 * 4];
+                let mut canvas = Canvas::new(&mut frame, 8, 8, 8, 8, coordinate_system, false, (0, 0, 0, 0));
+                canvas.draw_line(x0, y0, x1, y1, white);
+                let mut expected: Vec<_> = bresenham(x0, y0, x1, y1)
+                    .into_iter()
+                    .filter(|&(x, y)| canvas.to_logical_coords(x, y).is_some())
+                    .collect();
+                expected.sort();
+                assert_eq!(pixels_of(&canvas, white), expected, "{coordinate_system:?} ({x0}, {y0}) to ({x1}, {y1})");
+            }
+        }
+    }
+
+    #[test]
+    fn draw_line_with_extreme_end_points() {
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut canvas = new_canvas(&mut frame, (8, 8), (8, 8));
+        canvas.draw_line(i32::MIN, 3, i32::MAX, 3, (255, 255, 255, 255));
+        assert_eq!(pixels_of(&canvas, (255, 255, 255, 255)), (0..8).map(|x| (x, 3)).collect::<Vec<_>>());
+        canvas.draw_line(i32::MIN, i32::MIN, i32::MAX, i32::MAX, (255, 0, 0, 255));
+        assert_eq!(pixels_of(&canvas, (255, 0, 0, 255)), (0..8).map(|xy| (xy, xy)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn draw_axes_places_ticks_at_multiples_of_the_spacing() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        canvas.draw_axes((5, 8), (0, 15), (0, 15), 4, white);
+        let mut expected: Vec<_> = (0..16).map(|x| (x, 8)).chain((0..16).map(|y| (5, y))).collect();
+        for x in [1, 9, 13] {
+            expected.extend([(x, 7), (x, 9)]);
+        }
+        for y in [0, 4, 12] {
+            expected.extend([(4, y), (6, y)]);
+        }
+        expected.sort();
+        expected.dedup();
+        assert_eq!(pixels_of(&canvas, white), expected);
+    }
+
+    #[test]
+    fn drawing_over_a_tagged_pixel_untags_it() {
+        let mut frame = vec![0; 4 * 4 * 4];
+        let mut canvas = new_canvas(&mut frame, (4, 4), (4, 4));
+        canvas.set_pixel_id(1, 1, (255, 0, 0, 255), 7);
+        canvas.set_pixel_id(2, 1, (255, 0, 0, 255), 8);
+        canvas.set_pixel(1, 1, (0, 255, 0, 255));
+        assert_eq!(canvas.id_at(1, 1), None);
+        assert_eq!(canvas.id_at(2, 1), Some(8));
+
+        canvas.set_pixel_id(1, 1, (255, 0, 0, 255), 9);
+        assert_eq!(canvas.id_at(1, 1), Some(9));
+        canvas.clear((0, 0, 0, 255));
+        assert_eq!(canvas.id_at(1, 1), None);
+        assert_eq!(canvas.id_at(2, 1), None);
+    }
+
+    #[test]
+    fn fill_rect_f_floors_both_edges() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut canvas = new_canvas(&mut frame, (8, 8), (8, 8));
+        canvas.fill_rect_f(0.5, 0.0, 2.0, 1.0, white);
+        assert_eq!(pixels_of(&canvas, white), [(0, 0), (1, 0)]);
+
+        canvas.clear((0, 0, 0, 0));
+        canvas.fill_rect_f(1.5, 2.5, 2.0, 0.5, white);
+        assert_eq!(pixels_of(&canvas, white), [(1, 2), (2, 2)]);
+
+        canvas.clear((0, 0, 0, 0));
+        canvas.fill_rect_f(-1.0e30, 5.5, 2.0e30, 1.0, white);
+        assert_eq!(pixels_of(&canvas, white), (0..8).map(|x| (x, 5)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn airbrush_and_in_inset_near_the_coordinate_limits() {
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut canvas = new_canvas(&mut frame, (8, 8), (8, 8));
+        canvas.airbrush(3, 3, 100_000, (255, 255, 255, 255), 1.0);
+        assert!(pixels_of(&canvas, (0, 0, 0, 0)).is_empty());
+        canvas.airbrush(i32::MAX, i32::MIN, 10, (255, 0, 0, 255), 1.0);
+
+        let mut called = false;
+        canvas.in_inset(Rect { x: i32::MAX - 1, y: 0, width: u32::MAX, height: 4 }, 1, CoordinateSystem::TopLeft, |inset| {
+            called = true;
+            assert!(!inset.set_pixel(0, 0, (255, 0, 0, 255)));
+        });
+        assert!(called);
+        assert!(pixels_of(&canvas, (255, 0, 0, 255)).is_empty());
+    }
+
+    #[test]
+    fn buffer_size_bytes_of_a_known_size_canvas() {
+        let mut frame = vec![0; 8 * 4 * 4];
+        let mut canvas = new_canvas(&mut frame, (8, 4), (4, 2));
+        assert_eq!(canvas.buffer_size_bytes(), 128);
+        canvas.add_viewport("left", Viewport { x: 0, y: 0, width: 2, height: 2, scale: 1, coordinate_system: CoordinateSystem::TopLeft });
+        canvas.in_viewport("left", |viewport| assert_eq!(viewport.buffer_size_bytes(), 4 * 4 * 4));
+        assert_eq!(OwnedCanvas::new(3, 5).buffer_size_bytes(), 60);
+        assert_eq!(Sprite::new(2, 3, vec![(0, 0, 0, 0); 6]).buffer_size_bytes(), 24);
+    }
+
+    #[test]
+    fn pixel_scales_fit_the_aspect() {
+        assert_eq!(pixel_scales(320, 240, 80, 120, 2.0), (4, 2));
+        assert_eq!(pixel_scales(320, 240, 80, 60, 1.0), (4, 4));
+        // At 1.0 each axis fills the buffer on its own, even when the grid's shape differs
+        assert_eq!(pixel_scales(320, 240, 40, 60, 1.0), (8, 4));
+        assert_eq!(pixel_scales(320, 240, 40, 60, f32::NAN), (8, 4));
+        // Blocks never get wider than the buffer allows
+        assert_eq!(pixel_scales(320, 240, 80, 60, 1e9), (4, 1));
+        assert_eq!(pixel_scales(320, 240, 80, 60, 1e-9), (1, 4));
+        assert_eq!(pixel_scales(10, 10, 20, 20, 1.0), (1, 1));
+        assert_eq!(pixel_scales(320, 240, 0, 60, 1.0), (1, 1));
+        assert_eq!(pixel_scales(320, 240, 80, 0, 2.0), (1, 1));
+    }
+
+    #[test]
+    fn draw_safe_area_is_inset_from_every_edge() {
+        let white = (255, 255, 255, 255);
+        // The 8×6 outline from buffer pixel (2, 2) to (9, 7) in dashes of 4, starting at its
+        // top-left corner and running along the top, down the right side and back along the bottom
+        let top_left = [(2, 2), (3, 2), (4, 2), (5, 2), (9, 3), (9, 4), (9, 5), (9, 6), (2, 7), (3, 7), (4, 7), (5, 7)];
+        for coordinate_system in [CoordinateSystem::TopLeft, CoordinateSystem::Center] {
+            let mut frame = vec![0; 12 * 10 * 4];
+            let mut canvas = Canvas::new(&mut frame, 12, 10, 12, 10, coordinate_system, false, (0, 0, 0, 0));
+            canvas.draw_safe_area(2, white);
+            let mut drawn: Vec<(u32, u32)> = (0..10)
+                .flat_map(|y| (0..12).map(move |x| (x, y)))
+                .filter(|&(x, y)| canvas.get_logical_pixel(x, y) == Some(white))
+                .collect();
+            let mut expected = top_left.to_vec();
+            if coordinate_system == CoordinateSystem::Center {
+                // y points up, so the walk starts at the bottom-left corner instead
+                expected = expected.into_iter().map(|(x, y)| (x, 9 - y)).collect();
+            }
+            drawn.sort();
+            expected.sort();
+            assert_eq!(drawn, expected, "{coordinate_system:?}");
+        }
+    }
+
+    #[test]
+    fn stroke_polyline_far_off_canvas_is_clipped() {
+        for coordinate_system in [CoordinateSystem::TopLeft, CoordinateSystem::Center] {
+            let mut frame = vec![0; 8 * 8 * 4];
+            let mut canvas = Canvas::new(&mut frame, 8, 8, 8, 8, coordinate_system, false, (0, 0, 0, 0));
+            canvas.stroke_polyline(&[(-1.0e9, 0.0), (1.0e9, 0.0)], 1.0, (255, 255, 255, 255), LineJoin::Miter, LineCap::Butt);
+            let row: Vec<_> = (0..8).map(|x| canvas.get_logical_pixel(x, if coordinate_system == CoordinateSystem::Center { 4 } else { 0 })).collect();
+            assert!(row.iter().all(|&pixel| pixel == Some((255, 255, 255, 255))), "{coordinate_system:?}: {row:?}");
+        }
+    }
+
+    #[test]
+    fn flood_fill_to_border_fills_inside_a_circle() {
+        let (border, fill) = ((255, 255, 255, 255), (255, 0, 0, 255));
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        // A mixed interior, which a plain flood fill would stop at
+        canvas.set_pixel(8, 7, (0, 0, 255, 255));
+        canvas.draw_circle(8, 8, 5, border);
+        canvas.flood_fill_to_border(8, 8, fill, border);
+
+        for y in 0..16 {
+            for x in 0..16 {
+                // The outline is the rim of the inside pixels, so nothing outside may change
+                let pixel = canvas.get_pixel(x, y).unwrap();
+                if point_in_circle(x, y, 8, 8, 5) {
+                    assert!(pixel == border || pixel == fill, "({x}, {y})");
+                } else {
+                    assert_eq!(pixel, (0, 0, 0, 0), "({x}, {y})");
+                }
+            }
+        }
+        assert_eq!(canvas.get_pixel(8, 7), Some(fill));
+        assert_eq!(canvas.get_pixel(0, 0), Some((0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn histogram_of_a_solid_frame() {
+        let mut frame = vec![0; 6 * 4 * 4];
+        let mut canvas = new_canvas(&mut frame, (6, 4), (3, 2));
+        canvas.clear((10, 20, 30, 255));
+        let bins = canvas.histogram();
+        for (channel, value) in [10, 20, 30, 255].into_iter().enumerate() {
+            assert_eq!(bins[channel][value], 24, "channel {channel}");
+            assert_eq!(bins[channel].iter().sum::<u32>(), 24, "channel {channel}");
+        }
+    }
+
+    #[test]
+    fn threshold_at_128_splits_a_gradient() {
+        let (black, white) = ((0, 0, 0, 255), (255, 255, 255, 255));
+        let mut frame = vec![0; 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 1), (16, 1));
+        for x in 0..16 {
+            let gray = (x * 17) as u8;
+            canvas.set_pixel(x, 0, (gray, gray, gray, 255));
+        }
+        canvas.threshold(128, black, white);
+        for x in 0..16 {
+            // 7 × 17 = 119 is the last gray below the level, 8 × 17 = 136 the first above
+            let expected = if x < 8 { black } else { white };
+            assert_eq!(canvas.get_pixel(x, 0), Some(expected), "x = {x}");
+        }
+
+        // The level itself counts as above
+        canvas.clear((128, 128, 128, 255));
+        canvas.set_pixel(0, 0, (127, 127, 127, 255));
+        canvas.threshold(128, black, white);
+        assert_eq!(canvas.get_pixel(0, 0), Some(black));
+        assert_eq!(canvas.get_pixel(1, 0), Some(white));
+    }
+
+    #[test]
+    fn composite_replace_at_full_opacity_is_a_copy() {
+        let mut src = OwnedCanvas::new(3, 2);
+        {
+            let mut src_canvas = src.canvas();
+            src_canvas.set_pixel(0, 0, (255, 0, 0, 255));
+            src_canvas.set_pixel(1, 0, (0, 255, 0, 128));
+            src_canvas.set_pixel(2, 1, (0, 0, 255, 0));
+        }
+        let mut composited = vec![0; 6 * 4 * 4];
+        let mut copied = composited.clone();
+        let mut canvas = new_canvas(&mut composited, (6, 4), (6, 4));
+        canvas.clear((9, 9, 9, 255));
+        canvas.composite(2, 1, &src, BlendMode::Replace, 1.0);
+
+        let mut expected = new_canvas(&mut copied, (6, 4), (6, 4));
+        expected.clear((9, 9, 9, 255));
+        for y in 0..2 {
+            for x in 0..3 {
+                expected.set_pixel(2 + x as i32, 1 + y as i32, src.get_pixel(x, y).unwrap());
+            }
+        }
+        assert_eq!(composited, copied);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn to_png_base64_is_a_png_data_uri() {
+        let mut frame = vec![0; 2 * 2 * 4];
+        let canvas = new_canvas(&mut frame, (2, 2), (2, 2));
+        let uri = canvas.to_png_base64().unwrap();
+        // "iVBORw0KGgo" is the base64 encoding of the PNG signature
+        assert!(uri.starts_with("data:image/png;base64,iVBORw0KGgo"), "{uri}");
+    }
+
+    #[test]
+    fn pie_chart_of_two_equal_values_is_two_half_discs() {
+        let (first, second) = ((255, 0, 0, 255), (0, 0, 255, 255));
+        let mut frame = vec![0; 15 * 15 * 4];
+        let mut canvas = new_canvas(&mut frame, (15, 15), (15, 15));
+        canvas.draw_pie_chart(7, 7, 6, &[1.0, 1.0], &[first, second]);
+
+        let (mut first_count, mut second_count) = (0, 0);
+        for y in 0..15 {
+            for x in 0..15 {
+                let dy = y - 7;
+                let pixel = canvas.get_pixel(x, y).unwrap();
+                if !point_in_circle(x, y, 7, 7, 6) {
+                    assert_eq!(pixel, (0, 0, 0, 0), "({x}, {y})");
+                } else if dy > 0 {
+                    // Angles 0 to π, which point down in TopLeft
+                    assert_eq!(pixel, first, "({x}, {y})");
+                    first_count += 1;
+                } else if dy < 0 {
+                    assert_eq!(pixel, second, "({x}, {y})");
+                    second_count += 1;
+                } else {
+                    // The shared edge along the x axis goes to the slice drawn last
+                    assert_eq!(pixel, second, "({x}, {y})");
+                }
+            }
+        }
+        assert_eq!(first_count, second_count);
+    }
+
+    #[test]
+    fn draw_text_rotated_cw90_turns_the_glyph_clockwise() {
+        let white = (255, 255, 255, 255);
+        let mut upright_frame = vec![0; 12 * 12 * 4];
+        let mut rotated_frame = upright_frame.clone();
+        let mut upright = new_canvas(&mut upright_frame, (12, 12), (12, 12));
+        upright.draw_text(0, 0, "A", white);
+        let height = upright.text_height("A") as i32;
+        let mut rotated = new_canvas(&mut rotated_frame, (12, 12), (12, 12));
+        rotated.draw_text_rotated(0, 0, "A", white, Rotation::Cw90);
+
+        // Turning clockwise sends the upright pixel (u, v) to (height - 1 - v, u)
+        let mut expected: Vec<_> = pixels_of(&upright, white).into_iter().map(|(u, v)| (height - 1 - v, u)).collect();
+        expected.sort();
+        let mut transposed: Vec<_> = pixels_of(&upright, white).into_iter().map(|(u, v)| (v, u)).collect();
+        transposed.sort();
+        assert_eq!(pixels_of(&rotated, white), expected);
+        // 'A' is not symmetric about its diagonal, so this tells a rotation from a transpose
+        assert_ne!(expected, transposed);
+    }
+
+    #[test]
+    fn fill_pattern_with_a_single_pixel_is_clear() {
+        let color = (12, 34, 56, 78);
+        let sprite = Sprite::new(1, 1, vec![color]);
+        for coordinate_system in [CoordinateSystem::TopLeft, CoordinateSystem::Center] {
+            let mut patterned = vec![7; 6 * 4 * 4];
+            let mut cleared = patterned.clone();
+            Canvas::new(&mut patterned, 6, 4, 3, 2, coordinate_system, false, (0, 0, 0, 0)).fill_pattern(&sprite, (5, -3));
+            Canvas::new(&mut cleared, 6, 4, 3, 2, coordinate_system, false, (0, 0, 0, 0)).clear(color);
+            assert_eq!(patterned, cleared, "{coordinate_system:?}");
+        }
+    }
+
+    #[test]
+    fn draw_arrow_shaft_is_draw_line() {
+        let white = (255, 255, 255, 255);
+        for (from, to) in [((2, 3), (13, 9)), ((12, 1), (3, 14)), ((8, 2), (8, 12))] {
+            let mut arrow_frame = vec![0; 16 * 16 * 4];
+            let mut line_frame = arrow_frame.clone();
+            let mut arrow = new_canvas(&mut arrow_frame, (16, 16), (16, 16));
+            arrow.draw_arrow(from, to, 3, white);
+            let mut line = new_canvas(&mut line_frame, (16, 16), (16, 16));
+            line.draw_line(from.0, from.1, to.0, to.1, white);
+
+            let arrow_pixels = pixels_of(&arrow, white);
+            let line_pixels = pixels_of(&line, white);
+            assert!(arrow_pixels.contains(&from) && arrow_pixels.contains(&to));
+            assert!(line_pixels.iter().all(|pixel| arrow_pixels.contains(pixel)), "{from:?} to {to:?}");
+            // Everything else belongs to the head, within its size of the tip
+            for &(x, y) in arrow_pixels.iter().filter(|pixel| !line_pixels.contains(pixel)) {
+                assert!((x - to.0).abs() <= 3 && (y - to.1).abs() <= 3, "({x}, {y}) for {from:?} to {to:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn thumbnail_of_a_checkerboard_is_uniform_gray() {
+        let mut frame = vec![0; 4 * 4 * 4];
+        let mut canvas = new_canvas(&mut frame, (4, 4), (4, 4));
+        for y in 0..4 {
+            for x in 0..4 {
+                let value = if (x + y) % 2 == 0 { 0 } else { 255 };
+                canvas.set_pixel(x, y, (value, value, value, 255));
+            }
+        }
+        let thumbnail = canvas.thumbnail(2, 2);
+        assert_eq!(thumbnail.len(), 2 * 2 * 4);
+        assert!(thumbnail.chunks_exact(4).all(|pixel| pixel == [128, 128, 128, 255]), "{thumbnail:?}");
+    }
+
+    #[test]
+    fn fill_rect_blended_over_two_colors() {
+        let (red, blue, overlay) = ((255, 0, 0, 255), (0, 0, 255, 255), (255, 255, 255, 128));
+        let mut frame = vec![0; 8 * 4 * 4];
+        let mut canvas = new_canvas(&mut frame, (8, 4), (8, 4));
+        canvas.fill_rect(0, 0, 4, 4, red);
+        canvas.fill_rect(4, 0, 4, 4, blue);
+        canvas.fill_rect_blended(2, 1, 4, 2, overlay);
+
+        let over_red = BlendMode::Normal.blend(red, overlay);
+        let over_blue = BlendMode::Normal.blend(blue, overlay);
+        // Half way to white from either side
+        assert!(over_red.0 == 255 && over_red.1.abs_diff(128) <= 1 && over_red.2.abs_diff(128) <= 1);
+        assert!(over_blue.2 == 255 && over_blue.0.abs_diff(128) <= 1 && over_blue.1.abs_diff(128) <= 1);
+        for y in 0..4 {
+            for x in 0..8 {
+                let background = if x < 4 { red } else { blue };
+                let inside = (2..6).contains(&x) && (1..3).contains(&y);
+                let expected = match (inside, x < 4) {
+                    (false, _) => background,
+                    (true, true) => over_red,
+                    (true, false) => over_blue,
+                };
+                assert_eq!(canvas.get_pixel(x, y), Some(expected), "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn measure_text_of_two_lines_at_scale_2() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 32 * 32 * 4];
+        let mut canvas = new_canvas(&mut frame, (32, 32), (32, 32));
+        // The wider line is two glyphs and a gap, 5 + 1 + 5; two lines are a line height and a
+        // glyph tall, 8 + 7
+        assert_eq!(canvas.measure_text("AB\nC", 2), (22, 30));
+        assert_eq!(canvas.measure_text("AB\nC", 1), (canvas.text_width("AB\nC"), canvas.text_height("AB\nC")));
+
+        canvas.draw_text_scaled(0, 0, "AB\nC", white, 2);
+        let drawn = pixels_of(&canvas, white);
+        assert!(!drawn.is_empty());
+        assert!(drawn.iter().all(|&(x, y)| x < 22 && y < 30));
+        assert!(drawn.iter().any(|&(x, _)| x == 21) && drawn.iter().any(|&(_, y)| y >= 28));
+    }
+
+    #[test]
+    fn draw_minimap_outlines_the_viewport() {
+        let white = (255, 255, 255, 255);
+        let mut src = OwnedCanvas::new(16, 16);
+        src.canvas().clear(white);
+        for (coordinate_system, rect) in [
+            (CoordinateSystem::TopLeft, Rect::new(0, 0, 8, 8)),
+            (CoordinateSystem::Center, Rect::new(-4, -3, 8, 8)),
+        ] {
+            let mut frame = vec![0; 8 * 8 * 4];
+            let mut canvas = Canvas::new(&mut frame, 8, 8, 8, 8, coordinate_system, false, (0, 0, 0, 0));
+            // The top-right quarter of the source's upper half, at half scale
+            canvas.draw_minimap(rect, &src, Rect::new(4, 0, 8, 4));
+            for (index, pixel) in frame.chunks_exact(4).enumerate() {
+                let (column, row) = (index % 8, index / 8);
+                let outlined = (2..=5).contains(&column) && row <= 1;
+                let expected = if outlined { [0, 0, 0, 255] } else { [255; 4] };
+                assert_eq!(pixel, expected, "{coordinate_system:?} column {column} row {row}");
+            }
+        }
+    }
+
+    #[test]
+    fn draw_elbow_connector_routes_across_then_down() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut sharp = new_canvas(&mut frame, (8, 8), (8, 8));
+        sharp.draw_elbow_connector((1, 1), (6, 5), 0, white);
+        let mut expected: Vec<_> = (1..=6).map(|x| (x, 1)).chain((2..=5).map(|y| (6, y))).collect();
+        expected.sort();
+        assert_eq!(pixels_of(&sharp, white), expected);
+
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut rounded = new_canvas(&mut frame, (8, 8), (8, 8));
+        rounded.draw_elbow_connector((1, 1), (6, 5), 2, white);
+        let pixels = pixels_of(&rounded, white);
+        for end in [(1, 1), (4, 1), (6, 3), (6, 5)] {
+            assert!(pixels.contains(&end), "{end:?} missing from {pixels:?}");
+        }
+        assert!(!pixels.contains(&(6, 1)));
+        // Everything off the two legs belongs to the arc around (4, 3)
+        for &(x, y) in pixels.iter().filter(|&&(x, y)| !((y == 1 && x <= 4) || (x == 6 && y >= 3))) {
+            assert!((5..=6).contains(&x) && (1..=2).contains(&y), "({x}, {y}) is off the route");
+        }
+    }
+
+    #[test]
+    fn capture_region_round_trips_through_draw_sprite() {
+        // Both origins name logical column 2, row 1
+        for (coordinate_system, x, y) in [(CoordinateSystem::TopLeft, 2, 1), (CoordinateSystem::Center, -2, 3)] {
+            let original: Vec<u8> = (0..8 * 8).flat_map(|i| [i as u8, 100, 200 - i as u8, 255]).collect();
+            let mut frame = original.clone();
+            let mut canvas = Canvas::new(&mut frame, 8, 8, 8, 8, coordinate_system, false, (0, 0, 0, 0));
+            let sprite = canvas.capture_region(x, y, 4, 3);
+            assert_eq!((sprite.width(), sprite.height()), (4, 3));
+            canvas.clear((0, 0, 0, 0));
+            canvas.draw_sprite(x, y, &sprite, None);
+
+            for (index, pixel) in frame.chunks_exact(4).enumerate() {
+                let (column, row) = (index % 8, index / 8);
+                let inside = (2..6).contains(&column) && (1..4).contains(&row);
+                let expected = if inside { &original[index * 4..index * 4 + 4] } else { &[0; 4] };
+                assert_eq!(pixel, expected, "{coordinate_system:?} column {column} row {row}");
+            }
+        }
+
+        let mut frame = vec![0; 8 * 8 * 4];
+        let canvas = new_canvas(&mut frame, (8, 8), (8, 8));
+        let clipped = canvas.capture_region(-2, 6, 4, 4);
+        assert_eq!((clipped.width(), clipped.height()), (2, 2));
+    }
+
+    #[test]
+    fn draw_contour_of_a_linear_gradient_is_straight() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        // Rising by one per column, so 2.5 is crossed halfway between columns 2 and 3
+        let values: Vec<f32> = (0..4).flat_map(|_| (0..5).map(|col| col as f32)).collect();
+        assert!(canvas.draw_contour(0, 0, 5, 4, &values, 2.5, 4, white));
+        assert_eq!(pixels_of(&canvas, white), (0..=12).map(|y| (10, y)).collect::<Vec<_>>());
+
+        assert!(!canvas.draw_contour(0, 0, 5, 3, &values, 2.5, 4, white));
+    }
+
+    #[test]
+    fn physical_block_of_the_corner_pixels() {
+        // Corners in (top-left, top-right, bottom-left, bottom-right) order, then points just outside
+        let cases = [
+            (CoordinateSystem::TopLeft, [(0, 0), (3, 0), (0, 2), (3, 2)], [(-1, 0), (4, 0), (0, -1), (0, 3)]),
+            (CoordinateSystem::Center, [(-2, 1), (1, 1), (-2, -1), (1, -1)], [(-3, 0), (2, 0), (0, 2), (0, -2)]),
+        ];
+        for (coordinate_system, corners, outside) in cases {
+            let mut frame = vec![0; 12 * 9 * 4];
+            let canvas = Canvas::new(&mut frame, 12, 9, 4, 3, coordinate_system, false, (0, 0, 0, 0));
+            let blocks = corners.map(|(x, y)| canvas.physical_block(x, y));
+            let expected = [(0, 0), (9, 0), (0, 6), (9, 6)].map(|(px, py)| Some((px, py, 3, 3)));
+            assert_eq!(blocks, expected, "{coordinate_system:?}");
+            for (x, y) in outside {
+                assert_eq!(canvas.physical_block(x, y), None, "{coordinate_system:?} ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn composite_masked_through_a_half_black_half_white_mask() {
+        let (red, blue) = ((255, 0, 0, 255), (0, 0, 255, 255));
+        let mut src = OwnedCanvas::new(4, 2);
+        src.canvas().clear(red);
+        let mut mask = OwnedCanvas::new(4, 2);
+        mask.canvas().clear((0, 0, 0, 255));
+        mask.canvas().fill_rect(2, 0, 2, 2, (255, 255, 255, 255));
+
+        let mut frame = vec![0; 4 * 2 * 4];
+        let mut canvas = new_canvas(&mut frame, (4, 2), (4, 2));
+        canvas.clear(blue);
+        assert!(canvas.composite_masked(0, 0, &src, &mask));
+        let left: Vec<_> = (0..2).flat_map(|x| [(x, 0), (x, 1)]).collect();
+        assert_eq!(pixels_of(&canvas, blue), left);
+        assert_eq!(pixels_of(&canvas, red).len(), 4);
+
+        canvas.clear(blue);
+        assert!(!canvas.composite_masked(0, 0, &src, &OwnedCanvas::new(4, 1)));
+        assert_eq!(pixels_of(&canvas, blue).len(), 8);
+    }
+
+    #[test]
+    fn simulate_cvd_leaves_grays_unchanged() {
+        for kind in [CvdKind::Protanopia, CvdKind::Deuteranopia, CvdKind::Tritanopia] {
+            let original: Vec<u8> = (0..=255).flat_map(|level| [level, level, level, 255 - level]).collect();
+            let mut frame = original.clone();
+            new_canvas(&mut frame, (256, 1), (256, 1)).simulate_cvd(kind);
+            assert_eq!(frame, original, "{kind:?}");
+
+            let mut frame = vec![255, 0, 0, 128];
+            new_canvas(&mut frame, (1, 1), (1, 1)).simulate_cvd(kind);
+            assert_ne!(frame[..3], [255, 0, 0], "{kind:?}");
+            assert_eq!(frame[3], 128);
+        }
+    }
+
+    #[test]
+    fn dots_grid_marks_every_crossing_once() {
+        let mut frame = vec![0; 12 * 9 * 4];
+        let gray = (128, 128, 128, 255);
+        Canvas::new(&mut frame, 12, 9, 4, 3, CoordinateSystem::TopLeft, true, gray)
+            .with_grid_style(GridStyle::Dots)
+            .draw_grid();
+        let dots: Vec<_> = frame
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(_, pixel)| *pixel == [128, 128, 128, 255])
+            .map(|(index, _)| (index % 12, index / 12))
+            .collect();
+        // One more column and row of crossings than logical pixels, the last on the far edges
+        let expected: Vec<_> = [0, 3, 6, 8].into_iter().flat_map(|y| [0, 3, 6, 9, 11].map(|x| (x, y))).collect();
+        assert_eq!(dots, expected);
+        assert_eq!(dots.len(), (4 + 1) * (3 + 1));
+    }
+
+    #[test]
+    fn draw_colormap_legend_places_ticks_and_labels() {
+        let white = (255, 255, 255, 255);
+        let colormap = Colormap::new(vec![(0.0, (255, 0, 0, 255)), (1.0, (0, 0, 255, 255))]);
+        for orientation in [Orientation::Vertical, Orientation::Horizontal] {
+            let rect = match orientation {
+                Orientation::Vertical => Rect::new(2, 2, 4, 21),
+                Orientation::Horizontal => Rect::new(4, 2, 21, 4),
+            };
+            let mut frame = vec![0; 40 * 40 * 4];
+            let mut legend = new_canvas(&mut frame, (40, 40), (40, 40));
+            legend.draw_colormap_legend(rect, &colormap, 0.0, 10.0, 3, white, orientation);
+
+            // The same ticks and labels drawn by hand, at steps 0, 10 and 20 from the start
+            let mut expected_frame = vec![0; 40 * 40 * 4];
+            let mut expected = new_canvas(&mut expected_frame, (40, 40), (40, 40));
+            for (step, text) in [(0, "0"), (10, "5"), (20, "10")] {
+                match orientation {
+                    Orientation::Vertical => {
+                        let y = 22 - step;
+                        expected.draw_line(6, y, 7, y, white);
+                        expected.draw_text(9, y - GLYPH_HEIGHT as i32 / 2, text, white);
+                    }
+                    Orientation::Horizontal => {
+                        let x = 4 + step;
+                        expected.draw_line(x, 6, x, 7, white);
+                        expected.draw_text(x - expected.text_width(text) as i32 / 2, 9, text, white);
+                    }
+                }
+            }
+            assert_eq!(pixels_of(&legend, white), pixels_of(&expected, white), "{orientation:?}");
+        }
+    }
+
+    #[test]
+    fn draw_progress_bar_at_half_fills_half_the_interior() {
+        let (fg, bg, border) = ((0, 255, 0, 255), (40, 40, 40, 255), (255, 255, 255, 255));
+        let block = |xs: std::ops::Range<i32>, ys: std::ops::Range<i32>| -> Vec<(i32, i32)> {
+            xs.flat_map(|x| ys.clone().map(move |y| (x, y))).collect()
+        };
+        for (orientation, filled) in [
+            (Orientation::Horizontal, block(2..6, 2..6)),
+            (Orientation::Vertical, block(2..10, 4..6)),
+        ] {
+            let mut frame = vec![0; 12 * 8 * 4];
+            let mut canvas = new_canvas(&mut frame, (12, 8), (12, 8));
+            // An 8x4 interior inside the 1-pixel border
+            canvas.draw_progress_bar(Rect::new(1, 1, 10, 6), 0.5, fg, bg, Some(border), orientation);
+            assert_eq!(pixels_of(&canvas, fg), filled, "{orientation:?}");
+            assert_eq!(pixels_of(&canvas, bg).len(), 16, "{orientation:?}");
+            assert_eq!(pixels_of(&canvas, border).len(), 2 * 10 + 2 * 4, "{orientation:?}");
+        }
+    }
+
+    #[test]
+    fn draw_banner_draws_one_block_per_font_pixel() {
+        let white = (255, 255, 255, 255);
+        let mut text_frame = vec![0; 16 * 16 * 4];
+        let mut text = new_canvas(&mut text_frame, (16, 16), (16, 16));
+        text.draw_text(0, 0, "HI", white);
+        let lit = pixels_of(&text, white);
+        assert!(!lit.is_empty());
+
+        let mut frame = vec![0; 48 * 48 * 4];
+        let mut banner = new_canvas(&mut frame, (48, 48), (48, 48));
+        banner.draw_banner(1, 2, "HI", 2, white);
+        // Each lit pixel becomes a 2x2 block on a pitch of 3, leaving the gaps dark
+        let mut expected: Vec<_> = lit
+            .iter()
+            .flat_map(|&(x, y)| [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| (1 + x * 3 + dx, 2 + y * 3 + dy)))
+            .collect();
+        expected.sort();
+        assert_eq!(pixels_of(&banner, white), expected);
+        assert_eq!(expected.len(), lit.len() * 4);
+    }
+
+    #[test]
+    fn flash_at_zero_and_full_amount() {
+        let original: Vec<u8> = (0..6 * 4 * 4).map(|i| (i * 7 % 256) as u8).collect();
+        let color = (200, 10, 90, 128);
+
+        let mut frame = original.clone();
+        new_canvas(&mut frame, (6, 4), (3, 2)).flash(color, 0.0);
+        assert_eq!(frame, original);
+        new_canvas(&mut frame, (6, 4), (3, 2)).flash(color, -2.0);
+        assert_eq!(frame, original);
+
+        let mut cleared = original.clone();
+        new_canvas(&mut cleared, (6, 4), (3, 2)).clear(color);
+        for amount in [1.0, 3.0] {
+            let mut frame = original.clone();
+            new_canvas(&mut frame, (6, 4), (3, 2)).flash(color, amount);
+            assert_eq!(frame, cleared, "amount {amount}");
+        }
+    }
+
+    #[test]
+    fn fill_rect_stipple_checkerboard_tiles_from_the_origin() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut canvas = Canvas::new(&mut frame, 8, 8, 8, 8, CoordinateSystem::Center, false, (0, 0, 0, 0));
+        // Two adjacent fills covering the whole canvas, including negative coordinates
+        canvas.fill_rect_stipple(-4, -3, 3, 8, 0xaa55_aa55_aa55_aa55, white);
+        canvas.fill_rect_stipple(-1, -3, 5, 8, 0xaa55_aa55_aa55_aa55, white);
+        let mut expected: Vec<_> = (-4..4)
+            .flat_map(|x| (-3..5).map(move |y| (x, y)))
+            .filter(|&(x, y): &(i32, i32)| (x + y).rem_euclid(2) == 0)
+            .collect();
+        expected.sort();
+        assert_eq!(pixels_of(&canvas, white), expected);
+
+        canvas.fill_rect_stipple(-4, -3, 8, 8, u64::MAX, white);
+        assert_eq!(pixels_of(&canvas, white).len(), 64);
+    }
+
+    #[test]
+    fn draw_spline_through_collinear_points_stays_on_the_line() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut horizontal = new_canvas(&mut frame, (16, 16), (16, 16));
+        horizontal.draw_spline(&[(1.0, 3.0), (4.0, 3.0), (12.0, 3.0), (14.0, 3.0)], white, 0.0);
+        assert_eq!(pixels_of(&horizontal, white), (1..=14).map(|x| (x, 3)).collect::<Vec<_>>());
+
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut diagonal = new_canvas(&mut frame, (16, 16), (16, 16));
+        diagonal.draw_spline(&[(0.0, 0.0), (3.0, 3.0), (10.0, 10.0)], white, 0.5);
+        assert_eq!(pixels_of(&diagonal, white), (0..=10).map(|xy| (xy, xy)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn draw_graph_with_two_nodes_and_an_edge() {
+        let (red, blue) = ((255, 0, 0, 255), (0, 0, 255, 255));
+        let mut graph = Graph::new().with_node_radius(2);
+        let a = graph.add_node((4, 4), "A", red);
+        let b = graph.add_node((20, 4), "", blue);
+        graph.add_edge(a, b);
+        // Skipped rather than panicking
+        graph.add_edge(a, 7);
+        graph.add_edge(9, b);
+
+        let mut frame = vec![0; 24 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (24, 16), (24, 16));
+        canvas.draw_graph(&graph);
+
+        // The edge shows only between the circles, which cover its ends
+        assert_eq!(pixels_of(&canvas, graph.edge_color), (7..=17).map(|x| (x, 4)).collect::<Vec<_>>());
+        let mut expected_frame = vec![0; 24 * 16 * 4];
+        let mut expected = new_canvas(&mut expected_frame, (24, 16), (24, 16));
+        expected.fill_circle(4, 4, 2, red);
+        expected.fill_circle(20, 4, 2, blue);
+        expected.draw_text(4 - expected.text_width("A") as i32 / 2, 8, "A", graph.label_color);
+        for color in [red, blue, graph.label_color] {
+            assert_eq!(pixels_of(&canvas, color), pixels_of(&expected, color), "{color:?}");
+        }
+    }
+
+    #[test]
+    fn fill_sector_matches_fill_circle_and_clips() {
+        let white = (255, 255, 255, 255);
+        let mut circle_frame = vec![0; 16 * 16 * 4];
+        let mut circle = new_canvas(&mut circle_frame, (16, 16), (16, 16));
+        circle.fill_circle(7, 8, 5, white);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut halves = new_canvas(&mut frame, (16, 16), (16, 16));
+        halves.fill_sector(7, 8, 5, 0.0, std::f32::consts::PI, white);
+        halves.fill_sector(7, 8, 5, std::f32::consts::PI, std::f32::consts::TAU, white);
+        assert_eq!(pixels_of(&halves, white), pixels_of(&circle, white));
+
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut huge = new_canvas(&mut frame, (16, 16), (16, 16));
+        huge.fill_sector(0, 0, 50_000, 0.0, 1.0, white);
+        huge.fill_sector(i32::MAX, i32::MIN, u32::MAX, 0.0, 7.0, white);
+        assert!(!pixels_of(&huge, white).is_empty());
+    }
+
+    #[test]
+    fn draw_arc_aa_of_a_huge_ring_only_visits_the_canvas() {
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        // Far from the canvas, so nothing shows, but the loop must stay small to finish at all
+        canvas.draw_arc_aa(0.0, 0.0, 100_000.0, 1.0, 0.0, 1.0, (255, 255, 255, 255));
+        assert_eq!(pixels_of(&canvas, (0, 0, 0, 0)).len(), 16 * 16);
+        canvas.draw_arc_aa(8.0, 8.0, f32::MAX, 1e30, 0.0, 7.0, (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn draw_axes_over_the_full_i32_range() {
+        let white = (255, 255, 255, 255);
+        let mut expected_frame = vec![0; 16 * 16 * 4];
+        let mut expected = new_canvas(&mut expected_frame, (16, 16), (16, 16));
+        expected.draw_axes((5, 8), (0, 15), (0, 15), 4, white);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        let full = (i32::MIN, i32::MAX);
+        canvas.draw_axes((5, 8), full, full, 4, white);
+        assert_eq!(pixels_of(&canvas, white), pixels_of(&expected, white));
+
+        // Extreme origins and spacings neither overflow nor take long
+        canvas.draw_axes((0, 0), full, full, 1, white);
+        canvas.draw_axes_with_labels((i32::MIN, i32::MAX), full, full, u32::MAX, white);
+        canvas.draw_axes_with_labels((i32::MAX, i32::MIN), full, full, 3, white);
+    }
+
+    /// The midpoint-style outline of a circle: one octant's rim points mirrored eight ways
+    fn mirrored_octant(cx: i32, cy: i32, radius: u32) -> Vec<(i32, i32)> {
+        let limit = radius as i64 * radius as i64 + radius as i64;
+        let mut points = Vec::new();
+        for y in 0.. {
+            let x = ((limit - y * y) as f64).sqrt() as i64;
+            if x < y {
+                break;
+            }
+            let (x, y) = (x as i32, y as i32);
+            points.extend([(x, y), (y, x), (-y, x), (-x, y), (-x, -y), (-y, -x), (y, -x), (x, -y)]);
+        }
+        points.into_iter().map(|(dx, dy)| (cx + dx, cy + dy)).collect()
+    }
+
+    #[test]
+    fn draw_circle_is_the_mirrored_octant() {
+        let white = (255, 255, 255, 255);
+        for radius in 0..70 {
+            // Centered, and hanging off the top-left corner
+            for (cx, cy) in [(70, 70), (-(radius as i32) / 2, 3)] {
+                let mut frame = vec![0; 141 * 141 * 4];
+                let mut canvas = new_canvas(&mut frame, (141, 141), (141, 141));
+                canvas.draw_circle(cx, cy, radius, white);
+                let mut expected: Vec<_> = mirrored_octant(cx, cy, radius)
+                    .into_iter()
+                    .filter(|&(x, y)| (0..141).contains(&x) && (0..141).contains(&y))
+                    .collect();
+                expected.sort();
+                expected.dedup();
+                assert_eq!(pixels_of(&canvas, white), expected, "radius {radius} at ({cx}, {cy})");
+            }
+        }
+    }
+
+    #[test]
+    fn circles_far_off_or_huge_are_clipped() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        canvas.fill_circle(i32::MAX, 0, 5, white);
+        canvas.draw_circle(i32::MIN, i32::MAX, 5, white);
+        canvas.draw_rings(0, 0, 3_000_000_000, 2, white);
+        assert!(pixels_of(&canvas, white).is_empty());
+
+        canvas.fill_circle(0, 0, 200_000_000, white);
+        assert_eq!(pixels_of(&canvas, white).len(), 16 * 16);
+        canvas.clear((0, 0, 0, 0));
+        canvas.draw_circle(8, 8, u32::MAX, white);
+        canvas.fill_rings(8, 8, u32::MAX, 3, white, white);
+        assert_eq!(pixels_of(&canvas, white).len(), 16 * 16);
+    }
+
+    #[test]
+    fn fill_rect_stipple_clips_without_shifting_the_pattern() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 8 * 8 * 4];
+        let mut canvas = new_canvas(&mut frame, (8, 8), (8, 8));
+        canvas.fill_rect_stipple(i32::MAX - 2, 0, 10, 1, u64::MAX, white);
+        canvas.fill_rect_stipple(i32::MIN, i32::MIN, u32::MAX / 2, u32::MAX, u64::MAX, white);
+        assert!(pixels_of(&canvas, white).is_empty());
+
+        // Starting off the canvas gives the same pixels as starting on it
+        canvas.fill_rect_stipple(-5, -3, 13, 11, 0xaa55_aa55_aa55_aa55, white);
+        let mut expected_frame = vec![0; 8 * 8 * 4];
+        let mut expected = new_canvas(&mut expected_frame, (8, 8), (8, 8));
+        expected.fill_rect_stipple(0, 0, 8, 8, 0xaa55_aa55_aa55_aa55, white);
+        assert_eq!(pixels_of(&canvas, white), pixels_of(&expected, white));
+    }
+
+    #[test]
+    fn grid_sized_drawing_rejects_overflowing_dimensions() {
+        let mut frame = vec![0; 4 * 4 * 4];
+        let mut canvas = new_canvas(&mut frame, (4, 4), (4, 4));
+        assert!(!canvas.draw_cell_grid(0, 0, 70000, 70000, 1, 1, &[], 0));
+        assert!(!canvas.draw_vector_field(Rect::new(0, 0, 4, 4), 70000, 70000, &[], 1.0, (255, 255, 255, 255)));
+        assert!(!canvas.draw_contour(0, 0, 70000, 70000, &[], 0.5, 1, (255, 255, 255, 255)));
+        assert!(!canvas.draw_filled_contours(0, 0, 70000, 70000, &[], &[0.5], &Colormap::viridis(), 1));
+    }
+
+    #[test]
+    fn draw_arc_with_a_huge_radius_finishes() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        canvas.draw_arc(8, 8, u32::MAX, 0.0, std::f32::consts::TAU, white);
+        canvas.draw_arc(i32::MAX, i32::MIN, 1 << 30, 0.0, 1.0, white);
+        assert!(pixels_of(&canvas, white).is_empty());
+    }
+
+    #[test]
+    fn draw_spline_through_far_points_finishes() {
+        let white = (255, 255, 255, 255);
+        let mut frame = vec![0; 16 * 16 * 4];
+        let mut canvas = new_canvas(&mut frame, (16, 16), (16, 16));
+        canvas.draw_spline(&[(2.0, 2.0), (1e12, -1e12), (-1e12, 1e12), (12.0, 12.0)], white, 0.0);
+        assert_eq!(canvas.get_pixel(2, 2), Some(white));
+        assert_eq!(canvas.get_pixel(12, 12), Some(white));
     }
 }
@@ -0,0 +1,71 @@
+use crate::canvas::{lerp_color, Color};
+
+/// A continuous mapping from scalar values to colors, defined by color stops
+///
+/// Used to color scalar fields, as in `Canvas::draw_filled_contours`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colormap {
+    stops: Vec<(f32, Color)>,
+}
+
+impl Colormap {
+    /// Create a colormap from (position, color) stops, which are sorted by position
+    ///
+    /// Panics if `stops` is empty
+    pub fn new(mut stops: Vec<(f32, Color)>) -> Self {
+        assert!(!stops.is_empty(), "a colormap needs at least one stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// Create a colormap with the colors spread evenly from 0 to 1
+    ///
+    /// Panics if `colors` is empty
+    pub fn from_colors(colors: &[Color]) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Self::new(
+            colors
+                .iter()
+                .enumerate()
+                .map(|(i, &color)| (i as f32 / last, color))
+                .collect(),
+        )
+    }
+
+    /// Black at 0 to white at 1
+    pub fn grayscale() -> Self {
+        Self::from_colors(&[(0, 0, 0, 0xff), (0xff, 0xff, 0xff, 0xff)])
+    }
+
+    /// An approximation of matplotlib's perceptually uniform viridis map, dark purple to yellow
+    pub fn viridis() -> Self {
+        Self::from_colors(&[
+            (0x44, 0x01, 0x54, 0xff),
+            (0x3b, 0x52, 0x8b, 0xff),
+            (0x21, 0x91, 0x8c, 0xff),
+            (0x5e, 0xc9, 0x62, 0xff),
+            (0xfd, 0xe7, 0x25, 0xff),
+        ])
+    }
+
+    /// The (position, color) stops, sorted by position
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Look up the color at `t`, interpolating linearly between the two surrounding stops
+    ///
+    /// Values before the first stop or after the last take that stop's color
+    pub fn sample(&self, t: f32) -> Color {
+        let next = self.stops.partition_point(|&(position, _)| position <= t);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+        let (start, from) = self.stops[next - 1];
+        let (end, to) = self.stops[next];
+        lerp_color(from, to, (t - start) / (end - start))
+    }
+}
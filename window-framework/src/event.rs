@@ -0,0 +1,45 @@
+use winit::keyboard::KeyCode;
+
+/// A mouse button tracked by the framework
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+/// Something meaningful the framework observed or did, passed to `World::on_event`
+///
+/// A structured trace for diagnosing input and lifecycle issues; worlds should keep using
+/// `InputState` for gameplay. More variants may be added, so matches need a wildcard arm
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum FrameworkEvent {
+    /// A key went down (OS auto-repeat is not reported)
+    KeyPressed(KeyCode),
+    /// A key was released
+    KeyReleased(KeyCode),
+    /// A mouse button went down, with the cursor position in window coordinates if known
+    MouseClicked {
+        button: MouseButton,
+        position: Option<(f64, f64)>,
+    },
+    /// A mouse button was released
+    MouseReleased(MouseButton),
+    /// The window's inner size changed, in physical pixels
+    Resized { width: u32, height: u32 },
+    /// The display scale factor changed, e.g. after moving to a monitor with a different DPI
+    ScaleFactorChanged(f64),
+    /// The window gained (true) or lost (false) keyboard focus
+    FocusChanged(bool),
+    /// The window was minimized or hidden (true), or restored (false)
+    Minimized(bool),
+    /// A frame was drawn and presented; `frame` counts from 1 like `FrameStats::frame_count`
+    ///
+    /// Frames that nothing drew into are not presented, so they send no event
+    Redraw { frame: u64 },
+    /// An operation failed; `operation` names it (e.g. "pixels.render")
+    Error { operation: String, message: String },
+    /// The user asked to close the window
+    CloseRequested,
+}
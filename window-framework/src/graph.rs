@@ -0,0 +1,99 @@
+use crate::canvas::Color;
+
+/// How the edges of a `Graph` are routed between node centers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeStyle {
+    /// A straight line, see `Canvas::draw_line`
+    #[default]
+    Straight,
+    /// Horizontally then vertically with a rounded corner, see `Canvas::draw_elbow_connector`
+    Elbow { corner_radius: u32 },
+}
+
+/// A node of a `Graph`, drawn as a filled circle with an optional label under it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphNode {
+    /// Center of the node in user coordinates
+    pub position: (i32, i32),
+    /// Text drawn centered below the node; empty for none
+    pub label: String,
+    pub color: Color,
+}
+
+/// Nodes and the edges between them, laid out by the caller and drawn with `Canvas::draw_graph`
+///
+/// Edges are pairs of indices into `nodes` and are undirected as far as drawing is concerned.
+/// Nothing is validated when edges are added, so edges to missing nodes can be built up front
+/// and are simply skipped when drawing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<(usize, usize)>,
+    /// Radius of every node's circle (default 3)
+    pub node_radius: u32,
+    /// Color of the edges (default gray)
+    pub edge_color: Color,
+    pub edge_style: EdgeStyle,
+    /// Color of the node labels (default white)
+    pub label_color: Color,
+}
+
+impl Graph {
+    /// Create an empty graph with the default styling
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            node_radius: 3,
+            edge_color: (0x80, 0x80, 0x80, 0xff),
+            edge_style: EdgeStyle::Straight,
+            label_color: (0xff, 0xff, 0xff, 0xff),
+        }
+    }
+
+    /// Set the radius of the node circles
+    pub fn with_node_radius(mut self, node_radius: u32) -> Self {
+        self.node_radius = node_radius;
+        self
+    }
+
+    /// Set the color of the edges
+    pub fn with_edge_color(mut self, edge_color: Color) -> Self {
+        self.edge_color = edge_color;
+        self
+    }
+
+    /// Route the edges as straight lines or elbow connectors
+    pub fn with_edge_style(mut self, edge_style: EdgeStyle) -> Self {
+        self.edge_style = edge_style;
+        self
+    }
+
+    /// Set the color of the node labels
+    pub fn with_label_color(mut self, label_color: Color) -> Self {
+        self.label_color = label_color;
+        self
+    }
+
+    /// Add a node and return its index, for use in `add_edge`
+    pub fn add_node(
+        &mut self,
+        position: (i32, i32),
+        label: impl Into<String>,
+        color: Color,
+    ) -> usize {
+        self.nodes.push(GraphNode { position, label: label.into(), color });
+        self.nodes.len() - 1
+    }
+
+    /// Connect the nodes at two indices
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.edges.push((from, to));
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
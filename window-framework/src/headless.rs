@@ -0,0 +1,158 @@
+use std::io;
+use std::time::Instant;
+
+use crate::app::{draw_frame, draw_world, FrameLayout};
+use crate::input::InputState;
+use crate::timing::FrameTimer;
+use crate::world::{StartContext, World};
+
+/// Run a world without opening a window, passing each rendered frame to `on_frame`
+///
+/// The world goes through the same hooks as in the windowed loop: `on_start` once (with no
+/// monitor), then every frame `on_frame_stats`, `update` (`updates_per_frame` times) and `draw`,
+/// using the dimensions, coordinate system, grid, pixel aspect and smoothing from `W::config()`.
+/// Time runs on a fixed clock of `1 / fps` seconds per frame (`fps` is at least 1), so
+/// `FrameStats::frame_time` is exactly one interval after the first frame, nothing counts as
+/// dropped, and a time-based world renders the same however long each frame takes to produce.
+/// `World::time_source` is not consulted.
+/// No input is delivered, but widgets from `World::ui` are still drawn. `on_frame` receives the
+/// physical RGBA buffer with its width and height; the buffer persists between frames just like
+/// the window's. The world's `frame_sink` is fed as well.
+///
+/// Stops at the first error returned by `on_frame` and propagates it
+pub fn render_frames<W: World, E>(
+    frames: u32,
+    fps: u32,
+    mut on_frame: impl FnMut(&[u8], u32, u32) -> Result<(), E>,
+) -> Result<(), E> {
+    let config = W::config();
+    let mut world = W::new();
+    world.on_start(&StartContext { monitor_size: None });
+    let mut frame_sink = world.frame_sink();
+    let mut frame = vec![0; (config.width * config.height * 4) as usize];
+    let mut scene = Vec::new();
+    let mut input = InputState::new();
+    let mut frame_timer = FrameTimer::new(fps);
+    let interval = frame_timer.target_interval();
+
+    for index in 0..frames {
+        world.on_frame_stats(&frame_timer.tick(interval * index));
+        let update_start = Instant::now();
+        for _ in 0..world.updates_per_frame().unwrap_or(config.updates_per_frame).max(1) {
+            world.update();
+        }
+        let update_time = update_start.elapsed();
+
+        let draw_start = Instant::now();
+        let layout = FrameLayout {
+            width: config.width,
+            height: config.height,
+            pixel_grid_width: config.pixel_grid_width,
+            pixel_grid_height: config.pixel_grid_height,
+            coordinate_system: config.coordinate_system,
+            show_grid: world.show_grid().unwrap_or(config.show_grid),
+            grid_color: config.grid_color,
+            grid_style: config.grid_style,
+            pixel_aspect: config.pixel_aspect,
+        };
+        draw_frame(&mut world, &layout, &mut frame, &mut scene, config.smooth_upscale, &mut input);
+        frame_timer.record_phases(update_time, draw_start.elapsed());
+        if let Some(sink) = frame_sink.as_mut() {
+            sink(&frame, config.width, config.height);
+        }
+        on_frame(&frame, config.width, config.height)?;
+    }
+    Ok(())
+}
+
+/// Draw a world once into a new RGBA buffer of the given size, independent of any window
+///
+/// The dimensions from `W::config()` are overridden: the world draws onto a canvas with a
+/// `logical_width`×`logical_height` grid spread over `physical_width`×`physical_height`
+/// physical pixels, so a large physical size gives a high-resolution export of the same
+/// picture. The coordinate system, grid color and style, pixel aspect and smoothing still come
+/// from the config, and the grid is drawn if `show_grid` (the hook, or else the config) says so.
+/// Only the drawing phase runs: there is no `update`, and widgets from `World::ui` are not drawn
+/// since they need a mutable world.
+///
+/// Returns the tightly packed `physical_width * physical_height * 4` RGBA bytes, or an
+/// `InvalidInput` error if that many bytes don't fit in memory or exceed the 4 GiB a canvas can
+/// address
+pub fn render_world_to_buffer<W: World>(
+    world: &W,
+    logical_width: u32,
+    logical_height: u32,
+    physical_width: u32,
+    physical_height: u32,
+) -> io::Result<Vec<u8>> {
+    let size = (physical_width as usize)
+        .checked_mul(physical_height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .filter(|&size| u32::try_from(size).is_ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("a {physical_width}x{physical_height} RGBA buffer is too large"),
+            )
+        })?;
+    let config = W::config();
+    let layout = FrameLayout {
+        width: physical_width,
+        height: physical_height,
+        pixel_grid_width: logical_width,
+        pixel_grid_height: logical_height,
+        coordinate_system: config.coordinate_system,
+        show_grid: world.show_grid().unwrap_or(config.show_grid),
+        grid_color: config.grid_color,
+        grid_style: config.grid_style,
+        pixel_aspect: config.pixel_aspect,
+    };
+    let mut frame = vec![0; size];
+    if config.smooth_upscale {
+        // Smooth into the returned buffer so the grid drawn on it stays crisp
+        let mut scene = frame.clone();
+        let mut canvas = layout.canvas(&mut scene);
+        draw_world(world, &mut canvas);
+        canvas.smooth_upscale_into(&mut frame);
+    } else {
+        draw_world(world, &mut layout.canvas(&mut frame));
+    }
+    layout.canvas(&mut frame).draw_grid();
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::{Canvas, CoordinateSystem};
+    use crate::world::WorldConfig;
+
+    struct Solid;
+
+    impl World for Solid {
+        fn new() -> Self {
+            Solid
+        }
+
+        fn config() -> WorldConfig {
+            WorldConfig::new(4, 4, "solid", CoordinateSystem::TopLeft, None, None, false, (0, 0, 0, 0))
+        }
+
+        fn update(&mut self) {}
+
+        fn draw(&self, canvas: &mut Canvas) {
+            canvas.clear((10, 20, 30, 255));
+        }
+    }
+
+    #[test]
+    fn render_world_to_buffer_sizes() {
+        let frame = render_world_to_buffer(&Solid, 2, 2, 6, 4).unwrap();
+        assert_eq!(frame.len(), 6 * 4 * 4);
+        assert!(frame.chunks_exact(4).all(|pixel| pixel == [10, 20, 30, 255]));
+
+        let error = render_world_to_buffer(&Solid, 2, 2, u32::MAX, u32::MAX).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(render_world_to_buffer(&Solid, 2, 2, 65536, 16384).is_err());
+    }
+}
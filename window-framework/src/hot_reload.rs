@@ -0,0 +1,171 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use log::error;
+
+use crate::canvas::{Color, GridStyle};
+use crate::timing::TargetFps;
+use crate::world::WorldConfig;
+
+/// A problem found while parsing a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// 1-based line number of the offending line
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Apply the settings in a config file's text on top of `config`
+///
+/// The format is one `key = value` per line; blank lines and lines starting with `#` are
+/// ignored, and string values may be wrapped in double quotes. Recognized keys are `title`,
+/// `show_grid`, `grid_color` (`r, g, b` or `r, g, b, a`), `grid_style` (`lines` or `dots`),
+/// `target_fps` (a number or `auto`), `pixel_aspect`, `smooth_upscale`, `updates_per_frame`,
+/// `frame_budget_ms` (a number or `none`), `opaque` and `update_while_minimized`. Any other key
+/// is stored in `WorldConfig::params` for the world to interpret. Keys fixing the window or
+/// grid size cannot be changed once running and are rejected.
+///
+/// Returns the first malformed line as an error
+pub fn parse_config(text: &str, mut config: WorldConfig) -> Result<WorldConfig, ConfigError> {
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| ConfigError { line: index + 1, message };
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| error(format!("expected `key = value`, got `{line}`")))?;
+        let key = key.trim();
+        let value = unquote(value.trim());
+
+        match key {
+            "title" => config.title = value.to_string(),
+            "show_grid" => config.show_grid = parse_bool(value).map_err(error)?,
+            "grid_color" => config.grid_color = parse_color(value).map_err(error)?,
+            "grid_style" => config.grid_style = parse_grid_style(value).map_err(error)?,
+            "target_fps" => {
+                config.target_fps = if value.eq_ignore_ascii_case("auto") {
+                    TargetFps::Auto
+                } else {
+                    TargetFps::Fixed(parse_number(value).map_err(error)?)
+                }
+            }
+            "pixel_aspect" => config.pixel_aspect = parse_number(value).map_err(error)?,
+            "smooth_upscale" => config.smooth_upscale = parse_bool(value).map_err(error)?,
+            "updates_per_frame" => config.updates_per_frame = parse_number(value).map_err(error)?,
+            "frame_budget_ms" => {
+                config.frame_budget_ms = if value.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(parse_number(value).map_err(error)?)
+                }
+            }
+            "opaque" => config.opaque = parse_bool(value).map_err(error)?,
+            "update_while_minimized" => {
+                config.update_while_minimized = parse_bool(value).map_err(error)?
+            }
+            "width" | "height" | "pixel_grid_width" | "pixel_grid_height" | "coordinate_system"
+            | "position" | "window_size" => {
+                return Err(error(format!("`{key}` cannot be changed while running")))
+            }
+            _ => {
+                config.params.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    Ok(config)
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("expected `true` or `false`, got `{value}`")),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("`{value}` is not a valid number"))
+}
+
+fn parse_grid_style(value: &str) -> Result<GridStyle, String> {
+    match value {
+        "lines" => Ok(GridStyle::Lines),
+        "dots" => Ok(GridStyle::Dots),
+        _ => Err(format!("expected `lines` or `dots`, got `{value}`")),
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    let components = value
+        .split(',')
+        .map(|component| parse_number::<u8>(component.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match components[..] {
+        [r, g, b] => Ok((r, g, b, 255)),
+        [r, g, b, a] => Ok((r, g, b, a)),
+        _ => Err(format!("expected 3 or 4 color components, got `{value}`")),
+    }
+}
+
+/// Watches a config file by polling its modification time
+pub(crate) struct ConfigWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    /// The config from startup that every reload is applied on top of
+    base: WorldConfig,
+}
+
+impl ConfigWatcher {
+    pub(crate) fn new(path: PathBuf, base: WorldConfig) -> Self {
+        Self {
+            path,
+            last_modified: None,
+            base,
+        }
+    }
+
+    /// Re-read the file if it changed since the last poll, applying it on top of the base config
+    ///
+    /// Each reload starts again from the base snapshot, so only the fields the file sets differ
+    /// from startup and `World::config` is never called again.
+    ///
+    /// Returns None when the file is unchanged or missing, or when it fails to read or parse,
+    /// in which case the error is logged and the file isn't read again until it changes
+    pub(crate) fn poll(&mut self) -> Option<WorldConfig> {
+        let modified = fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok()?;
+        if self.last_modified == Some(modified) {
+            return None;
+        }
+        self.last_modified = Some(modified);
+
+        let result = fs::read_to_string(&self.path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| parse_config(&text, self.base.clone()).map_err(|err| err.to_string()));
+        match result {
+            Ok(config) => Some(config),
+            Err(err) => {
+                error!("failed to load {}: {err}; keeping the previous config", self.path.display());
+                None
+            }
+        }
+    }
+}
@@ -1,17 +1,82 @@
 use std::collections::HashSet;
-use winit::keyboard::KeyCode;
+use winit::keyboard::{Key, KeyCode};
+
+use crate::canvas::{PixelMapping, NO_ID};
 
 /// Tracks the current input state
-#[derive(Default)]
 pub struct InputState {
     /// Set of keys currently pressed
     pub keys_pressed: HashSet<KeyCode>,
 
-    /// Mouse position in window coordinates (None if outside window)
+    /// Keys that went down since the previous frame (OS auto-repeat is not counted)
+    pub keys_just_pressed: HashSet<KeyCode>,
+
+    /// Keys that produced an OS auto-repeat event since the previous frame
+    pub keys_repeated: HashSet<KeyCode>,
+
+    /// Logical keys currently pressed, i.e. what the held keys produce under the active layout
+    ///
+    /// Each key is recorded as it was when pressed, so changing modifiers while holding it does
+    /// not change the entry (pressing Shift after W keeps "w")
+    pub logical_keys_pressed: HashSet<Key>,
+
+    /// Text typed since the previous frame, in order and including OS auto-repeat
+    ///
+    /// Control characters are left out, so check Enter, Backspace and Tab as keys with
+    /// `is_key_just_pressed_or_repeated`. While `World::captures_text_input` is true, keys that
+    /// typed text only show up here and not in the key sets
+    pub text_input: String,
+
+    /// Mouse position in window coordinates, in physical screen pixels (None if outside window)
     pub mouse_position: Option<(f64, f64)>,
 
     /// Mouse buttons currently pressed (left, middle, right)
     pub mouse_buttons: (bool, bool, bool),
+
+    /// Mouse buttons pressed since the previous frame (left, middle, right)
+    pub mouse_buttons_just_pressed: (bool, bool, bool),
+
+    /// Mouse position in physical frame buffer pixels (None if outside the buffer)
+    pub mouse_buffer_position: Option<(u32, u32)>,
+
+    /// Ratio of physical screen pixels to the OS's logical (DPI-independent) pixels
+    ///
+    /// 2.0 on a typical HiDPI display. This is unrelated to the canvas's logical pixel grid:
+    /// divide `mouse_position` by it to get OS logical coordinates. Use `mouse_buffer_position`
+    /// or `picked_pixel` to find what is under the cursor, as those already account for it
+    pub scale_factor: f64,
+
+    /// Mapping from buffer pixels to user coordinates, taken from the most recently drawn canvas
+    ///
+    /// `App` refreshes this after every frame's canvas is built, so during `handle_input` it
+    /// describes the previous frame (None before the first frame has been drawn)
+    pub pixel_mapping: Option<PixelMapping>,
+
+    /// Object ids the previous frame's canvas was tagged with, see `picked_id`
+    pub(crate) ids: Vec<u32>,
+
+    /// Estimate from the previous frame, see `buffer_size_bytes`
+    pub(crate) buffer_size_bytes: usize,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self {
+            keys_pressed: HashSet::new(),
+            keys_just_pressed: HashSet::new(),
+            keys_repeated: HashSet::new(),
+            logical_keys_pressed: HashSet::new(),
+            text_input: String::new(),
+            mouse_position: None,
+            mouse_buttons: (false, false, false),
+            mouse_buttons_just_pressed: (false, false, false),
+            mouse_buffer_position: None,
+            scale_factor: 1.0,
+            pixel_mapping: None,
+            ids: Vec::new(),
+            buffer_size_bytes: 0,
+        }
+    }
 }
 
 impl InputState {
@@ -24,6 +89,77 @@ impl InputState {
         self.keys_pressed.contains(&key)
     }
 
+    /// Check if a logical key (what a key produces under the current keyboard layout) is pressed
+    ///
+    /// Complements the position-based `is_key_pressed`: on an AZERTY keyboard
+    /// `is_logical_key_pressed(&Key::Character("a".into()))` follows the key labelled A, while
+    /// `KeyCode::KeyA` is the key in the QWERTY A position (labelled Q)
+    pub fn is_logical_key_pressed(&self, key: &Key) -> bool {
+        self.logical_keys_pressed.contains(key)
+    }
+
+    /// Check if a key producing the given character is pressed, ignoring letter case
+    ///
+    /// `is_char_pressed('w')` is true for the key labelled W on any layout, with or without Shift
+    pub fn is_char_pressed(&self, c: char) -> bool {
+        let wanted: String = c.to_lowercase().collect();
+        self.logical_keys_pressed.iter().any(|key| match key {
+            Key::Character(text) => text.to_lowercase() == wanted,
+            _ => false,
+        })
+    }
+
+    /// Get the user coordinates of the logical pixel under the mouse cursor
+    ///
+    /// Uses the canvas's coordinate system and pixel scale via `pixel_mapping`, so it returns
+    /// None until the first frame has been drawn, and when the cursor is outside the logical grid
+    pub fn picked_pixel(&self) -> Option<(i32, i32)> {
+        let (phys_x, phys_y) = self.mouse_buffer_position?;
+        self.pixel_mapping?.physical_to_user(phys_x, phys_y)
+    }
+
+    /// Get the object id that the previous frame tagged the logical pixel under the cursor with
+    ///
+    /// `App` keeps the ids written with `Canvas::set_pixel_id` during each frame's `draw` (and
+    /// `ui`), so `handle_input` can tell which object the cursor is over. Returns None when the
+    /// pixel was not tagged, the cursor is outside the grid, or nothing has been drawn yet
+    pub fn picked_id(&self) -> Option<u32> {
+        let (phys_x, phys_y) = self.mouse_buffer_position?;
+        let mapping = self.pixel_mapping?;
+        let (logical_x, logical_y) = mapping.physical_to_logical(phys_x, phys_y)?;
+        self.ids
+            .get((logical_y * mapping.logical_width + logical_x) as usize)
+            .copied()
+            .filter(|&id| id != NO_ID)
+    }
+
+    /// Rough number of bytes allocated for pixel buffers, as of the previous frame
+    ///
+    /// Counts the frame buffer worlds draw into and the GPU texture it is uploaded to (the same
+    /// size), the unsmoothed copy kept while `WorldConfig::smooth_upscale` is on, the object id
+    /// buffer, and whatever the world reports through `World::buffer_size_bytes` for its own
+    /// off-screen canvases and recordings. The window surface's swapchain images are managed by
+    /// the graphics driver and not included. 0 before the first frame has been drawn
+    pub fn buffer_size_bytes(&self) -> usize {
+        self.buffer_size_bytes
+    }
+
+    /// Check if a key went down since the previous frame
+    ///
+    /// Only genuine presses count: holding a key down does not retrigger this through the OS
+    /// auto-repeat, so it fires once per physical press
+    pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+
+    /// Check if a key went down or auto-repeated since the previous frame
+    ///
+    /// Use this instead of `is_key_just_pressed` for behavior that should repeat while the key is
+    /// held, like moving a text cursor
+    pub fn is_key_just_pressed_or_repeated(&self, key: KeyCode) -> bool {
+        self.keys_just_pressed.contains(&key) || self.keys_repeated.contains(&key)
+    }
+
     /// Check if left mouse button is pressed
     pub fn is_left_mouse_pressed(&self) -> bool {
         self.mouse_buttons.0
@@ -38,4 +174,27 @@ impl InputState {
     pub fn is_right_mouse_pressed(&self) -> bool {
         self.mouse_buttons.2
     }
+
+    /// Check if left mouse button was pressed since the previous frame
+    pub fn is_left_mouse_just_pressed(&self) -> bool {
+        self.mouse_buttons_just_pressed.0
+    }
+
+    /// Check if middle mouse button was pressed since the previous frame
+    pub fn is_middle_mouse_just_pressed(&self) -> bool {
+        self.mouse_buttons_just_pressed.1
+    }
+
+    /// Check if right mouse button was pressed since the previous frame
+    pub fn is_right_mouse_just_pressed(&self) -> bool {
+        self.mouse_buttons_just_pressed.2
+    }
+
+    /// Forget the per-frame transitions once a frame has been processed
+    pub(crate) fn end_frame(&mut self) {
+        self.keys_just_pressed.clear();
+        self.keys_repeated.clear();
+        self.text_input.clear();
+        self.mouse_buttons_just_pressed = (false, false, false);
+    }
 }
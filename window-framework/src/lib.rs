@@ -1,6 +1,29 @@
 pub mod app;
+#[cfg(feature = "apng")]
+pub mod apng;
+pub mod blend;
 pub mod canvas;
+pub mod colormap;
+pub mod event;
+pub mod font;
+pub mod graph;
+pub mod headless;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
 pub mod input;
+pub mod lut;
+pub mod owned_canvas;
+#[cfg(feature = "png")]
+pub mod png;
+pub mod rng;
+pub mod scene;
+pub mod sprite;
+pub mod stroke;
+pub mod timeline;
+pub mod timing;
+pub mod ui;
+#[cfg(feature = "video")]
+pub mod video;
 pub mod world;
 
 use winit::{
@@ -9,15 +32,38 @@ use winit::{
 };
 
 pub use app::App;
-pub use canvas::{Canvas, CoordinateSystem};
+#[cfg(feature = "apng")]
+pub use apng::render_to_apng;
+pub use blend::BlendMode;
+pub use canvas::{luminance, pixel_scales, point_in_circle, Canvas, Channel, Color, CoordinateSystem, CvdKind, GridStyle, Orientation, PixelMapping, Rect, Rotation, SpiralKind, Viewport};
+pub use colormap::Colormap;
+pub use event::{FrameworkEvent, MouseButton};
+pub use graph::{EdgeStyle, Graph, GraphNode};
+pub use headless::{render_frames, render_world_to_buffer};
+#[cfg(feature = "hot-reload")]
+pub use hot_reload::{parse_config, ConfigError};
 pub use input::InputState;
-pub use world::{World, WorldConfig};
+pub use lut::ColorLut;
+pub use owned_canvas::OwnedCanvas;
+#[cfg(feature = "png")]
+pub use png::{encode_base64, encode_png, render_contact_sheet};
+pub use rng::Rng;
+pub use scene::{Node, NodeId, Scene, Shape};
+pub use sprite::{AnimatedSprite, Sprite, SpriteRegion};
+pub use stroke::{LineCap, LineJoin};
+pub use timeline::{Easing, Keyframe, Lerp, Timeline};
+pub use timing::{FrameStats, TargetFps};
+pub use ui::Ui;
+#[cfg(feature = "video")]
+pub use video::render_to_mp4;
+pub use world::{FrameSink, StartContext, TimeSource, World, WorldConfig};
+pub use winit::keyboard::{Key, KeyCode, NamedKey};
 
 /// Run a visualization with the given World implementation
 pub fn run<W: World + 'static>() -> Result<(), EventLoopError> {
     let config = W::config();
     let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Poll);
+    event_loop.set_control_flow(ControlFlow::Wait);
     let mut app = App::<W>::new(
         config.width,
         config.height,
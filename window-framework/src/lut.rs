@@ -0,0 +1,78 @@
+use crate::canvas::Color;
+
+/// A per-channel color lookup table, mapping each 0-255 value of R, G and B to a new value
+///
+/// Applied with `Canvas::apply_lut`. Alpha is never changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorLut {
+    tables: [[u8; 256]; 3],
+}
+
+impl ColorLut {
+    /// A table that maps every value to itself
+    pub fn identity() -> Self {
+        Self::from_curve(|value| value)
+    }
+
+    /// A table applying the same curve to all three color channels
+    pub fn from_curve(curve: impl Fn(u8) -> u8) -> Self {
+        let table = std::array::from_fn(|value| curve(value as u8));
+        Self { tables: [table; 3] }
+    }
+
+    /// A table with a separate curve for each of red, green and blue
+    pub fn from_channel_curves(
+        red: impl Fn(u8) -> u8,
+        green: impl Fn(u8) -> u8,
+        blue: impl Fn(u8) -> u8,
+    ) -> Self {
+        Self {
+            tables: [
+                std::array::from_fn(|value| red(value as u8)),
+                std::array::from_fn(|value| green(value as u8)),
+                std::array::from_fn(|value| blue(value as u8)),
+            ],
+        }
+    }
+
+    /// Reduce every channel to `levels` evenly spaced values, always including 0 and 255
+    ///
+    /// Each value snaps to the nearest level. Fewer than 2 levels is treated as 2
+    pub fn posterize(levels: u8) -> Self {
+        let steps = levels.max(2) as u32 - 1;
+        Self::from_curve(|value| {
+            let level = (value as u32 * steps + 127) / 255;
+            (level * 255 / steps) as u8
+        })
+    }
+
+    /// Map every channel value below `level` to 0 and all others to 255
+    pub fn threshold(level: u8) -> Self {
+        Self::from_curve(|value| if value < level { 0 } else { 255 })
+    }
+
+    /// Apply this table after another one, giving a single table that does both
+    pub fn then(&self, next: &ColorLut) -> Self {
+        Self {
+            tables: std::array::from_fn(|channel| {
+                std::array::from_fn(|value| next.tables[channel][self.tables[channel][value] as usize])
+            }),
+        }
+    }
+
+    /// Map a single color through the table
+    pub fn map(&self, color: Color) -> Color {
+        (
+            self.tables[0][color.0 as usize],
+            self.tables[1][color.1 as usize],
+            self.tables[2][color.2 as usize],
+            color.3,
+        )
+    }
+}
+
+impl Default for ColorLut {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
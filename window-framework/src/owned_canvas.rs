@@ -0,0 +1,81 @@
+use crate::canvas::{Canvas, Color, CoordinateSystem};
+
+/// An off-screen canvas that owns its frame buffer
+///
+/// Each logical pixel maps to exactly one physical pixel. Use `canvas` to draw into it with the
+/// regular `Canvas` API, then composite it onto the window canvas with `Canvas::composite`.
+pub struct OwnedCanvas {
+    frame: Vec<u8>,
+    width: u32,
+    height: u32,
+    coordinate_system: CoordinateSystem,
+}
+
+impl OwnedCanvas {
+    /// Create a new off-screen canvas filled with transparent black
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            frame: vec![0; (width * height * 4) as usize],
+            width,
+            height,
+            coordinate_system: CoordinateSystem::TopLeft,
+        }
+    }
+
+    /// Use the given coordinate system when drawing through `canvas`
+    pub fn with_coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        self.coordinate_system = coordinate_system;
+        self
+    }
+
+    /// Get the width of the canvas in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of the canvas in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Size of the owned frame buffer in bytes (`width * height * 4`)
+    pub fn buffer_size_bytes(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Get the raw RGBA frame buffer
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Get the color of a pixel in buffer coordinates (top-left origin)
+    ///
+    /// Returns None if the coordinates are out of bounds
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x < self.width && y < self.height {
+            let idx = ((y * self.width + x) * 4) as usize;
+            Some((
+                self.frame[idx],
+                self.frame[idx + 1],
+                self.frame[idx + 2],
+                self.frame[idx + 3],
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Borrow the buffer as a `Canvas` for drawing
+    pub fn canvas(&mut self) -> Canvas<'_> {
+        Canvas::new(
+            &mut self.frame,
+            self.width,
+            self.height,
+            self.width,
+            self.height,
+            self.coordinate_system,
+            false,
+            (0, 0, 0, 0),
+        )
+    }
+}
@@ -0,0 +1,185 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::headless::render_frames;
+use crate::world::World;
+
+/// PNG file signature
+pub(crate) const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest payload of a single stored (uncompressed) deflate block
+const MAX_STORED_BLOCK: usize = 65535;
+
+/// Lookup table for the CRC-32 used by PNG chunks
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// Encode an RGBA8 image as PNG bytes
+///
+/// The image data is stored without compression, which keeps the encoder tiny and dependency
+/// free at the cost of file size. `rgba` must hold exactly `width * height * 4` bytes.
+///
+/// Returns an `InvalidInput` error for empty images or a buffer of the wrong length
+pub fn encode_png(width: u32, height: u32, rgba: &[u8]) -> io::Result<Vec<u8>> {
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header(width, height, rgba)?);
+    write_chunk(&mut png, b"IDAT", &image_data(width, rgba));
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+/// Render a world headlessly and save the listed frames tiled into one PNG contact sheet
+///
+/// `frames` are 0-based indices into the sequence `render_frames` produces (frame 0 is drawn
+/// after the first round of updates); they may repeat and come in any order, and each is placed
+/// in the next cell, filling rows of `cols` cells left to right. Cells are the full physical
+/// frame size from `W::config()` and frames are copied 1:1 without scaling, so the sheet is
+/// `cols * width` × `ceil(frames.len() / cols) * height` pixels, with unused cells in the last
+/// row left transparent. The world runs up to the highest listed index, on a fixed clock at the
+/// config's target frame rate (60 for `TargetFps::Auto`).
+///
+/// Returns an `InvalidInput` error if `frames` is empty or `cols` is 0, and any error from
+/// writing the file
+pub fn render_contact_sheet<W: World>(
+    frames: &[u32],
+    cols: u32,
+    path: impl AsRef<Path>,
+) -> io::Result<()> {
+    let invalid = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+    let Some(&last) = frames.iter().max() else {
+        return Err(invalid("no frames to put on the contact sheet"));
+    };
+    if cols == 0 {
+        return Err(invalid("a contact sheet needs at least one column"));
+    }
+    let cols = cols as usize;
+    let config = W::config();
+    let (cell_width, cell_height) = (config.width as usize, config.height as usize);
+    let rows = frames.len().div_ceil(cols);
+    let sheet_width = cols * cell_width;
+    let mut sheet = vec![0; sheet_width * rows * cell_height * 4];
+
+    let mut index = 0;
+    let fps = config.target_fps.resolve(None);
+    render_frames::<W, io::Error>(last + 1, fps, |frame, _, _| {
+        let cells = frames.iter().enumerate().filter(|&(_, &wanted)| wanted == index);
+        for (cell, _) in cells {
+            let (left, top) = (cell % cols * cell_width, cell / cols * cell_height);
+            for (row, pixels) in frame.chunks_exact(cell_width * 4).enumerate() {
+                let start = ((top + row) * sheet_width + left) * 4;
+                sheet[start..start + pixels.len()].copy_from_slice(pixels);
+            }
+        }
+        index += 1;
+        Ok(())
+    })?;
+
+    let png = encode_png(sheet_width as u32, (rows * cell_height) as u32, &sheet)?;
+    fs::write(path, png)
+}
+
+/// Encode bytes as standard, padded base64
+pub fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let b = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+        let bits = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Validate the image size and build the IHDR payload
+pub(crate) fn header(width: u32, height: u32, rgba: &[u8]) -> io::Result<Vec<u8>> {
+    if width == 0 || height == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "PNG images cannot be empty"));
+    }
+    if rgba.len() as u64 != width as u64 * height as u64 * 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("expected {width}x{height} RGBA pixels, got {} bytes", rgba.len()),
+        ));
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, truecolor with alpha, deflate, standard filters, no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    Ok(header)
+}
+
+/// Zlib stream of the scanlines, each prefixed with filter type 0 (none)
+pub(crate) fn image_data(width: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(rgba.len() + rgba.len() / (width as usize * 4));
+    for row in rgba.chunks_exact(width as usize * 4) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    // Zlib header for deflate with a 32K window and no preset dictionary
+    let mut zlib = vec![0x78, 0x01];
+    let block_count = raw.len().div_ceil(MAX_STORED_BLOCK).max(1);
+    for (i, block) in raw.chunks(MAX_STORED_BLOCK).enumerate() {
+        zlib.push((i + 1 == block_count) as u8);
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    zlib
+}
+
+/// Append a chunk with its length and CRC
+pub(crate) fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 is the largest run that cannot overflow before taking the modulus
+    for run in bytes.chunks(5552) {
+        for &byte in run {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+    b << 16 | a
+}
@@ -0,0 +1,103 @@
+/// A small, fast, seedable pseudo-random number generator
+///
+/// The same seed always produces the same sequence, on every platform, which keeps demos and
+/// simulations reproducible. Based on SplitMix64; not suitable for cryptography
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Next 32 random bits
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Uniform float in 0.0..1.0
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Uniform integer in 0..bound, without modulo bias (0 when `bound` is 0)
+    pub fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+        let bound = bound as u64;
+        // Reject the top sliver of values that would make smaller results more likely
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return (value % bound) as usize;
+            }
+        }
+    }
+
+    /// Put the elements of a slice in random order (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            slice.swap(i, self.below(i + 1));
+        }
+    }
+
+    /// Pick `k` distinct elements (by position) of a slice, in random order
+    ///
+    /// Returns every element, shuffled, when `k` is at least the slice length
+    pub fn sample<T: Copy>(&mut self, slice: &[T], k: usize) -> Vec<T> {
+        let mut pool = slice.to_vec();
+        let k = k.min(pool.len());
+        // Partial Fisher-Yates: only the first k positions need to be settled
+        for i in 0..k {
+            let j = i + self.below(pool.len() - i);
+            pool.swap(i, j);
+        }
+        pool.truncate(k);
+        pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_seed_gives_the_splitmix64_sequence() {
+        let mut rng = Rng::new(0);
+        // Reference outputs of SplitMix64 seeded with 0
+        assert_eq!(rng.next_u64(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(rng.next_u64(), 0x06c4_5d18_8009_454f);
+    }
+
+    #[test]
+    fn shuffle_and_sample_are_stable_for_a_seed() {
+        // Pinned so that a change to `below`, `shuffle` or `sample` that reorders results for
+        // existing seeds shows up here
+        let mut rng = Rng::new(42);
+        let mut values: Vec<u32> = (0..10).collect();
+        rng.shuffle(&mut values);
+        assert_eq!(values, [0, 9, 5, 8, 6, 4, 7, 2, 1, 3]);
+        let pool: Vec<u32> = (10..20).collect();
+        assert_eq!(rng.sample(&pool, 4), [14, 19, 18, 10]);
+
+        let mut everything = rng.sample(&pool, 50);
+        everything.sort();
+        assert_eq!(everything, pool);
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::Cell;
+
+use crate::canvas::{Canvas, Color};
+use crate::sprite::Sprite;
+use crate::stroke::{LineCap, LineJoin};
+
+/// Something drawable, positioned relative to an origin
+///
+/// Each variant maps onto one canvas primitive; `draw` places the shape's origin at the given
+/// point in user coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A filled rectangle with its origin at the corner `Canvas::fill_rect` starts from
+    Rect { width: u32, height: u32, color: Color },
+    /// A 1-pixel line from the origin to `to` (relative to the origin)
+    Line { to: (i32, i32), color: Color },
+    /// A circle centered on the origin, outlined or filled
+    Circle { radius: u32, color: Color, filled: bool },
+    /// Text whose first line has its top-left pixel at the origin
+    Text { text: String, color: Color },
+    /// A sprite with its top-left pixel at the origin, see `Canvas::draw_sprite`
+    Sprite { sprite: Sprite, color_key: Option<Color> },
+    /// An anti-aliased stroke through points relative to the origin, see `Canvas::stroke_polyline`
+    Polyline {
+        points: Vec<(f32, f32)>,
+        width: f32,
+        color: Color,
+        join: LineJoin,
+        cap: LineCap,
+    },
+}
+
+impl Shape {
+    /// Draw the shape with its origin at (x, y)
+    pub fn draw(&self, canvas: &mut Canvas, x: i32, y: i32) {
+        match self {
+            Shape::Rect { width, height, color } => canvas.fill_rect(x, y, *width, *height, *color),
+            Shape::Line { to, color } => canvas.draw_line(x, y, x + to.0, y + to.1, *color),
+            Shape::Circle { radius, color, filled: true } => canvas.fill_circle(x, y, *radius, *color),
+            Shape::Circle { radius, color, filled: false } => canvas.draw_circle(x, y, *radius, *color),
+            Shape::Text { text, color } => canvas.draw_text(x, y, text, *color),
+            Shape::Sprite { sprite, color_key } => canvas.draw_sprite(x, y, sprite, *color_key),
+            Shape::Polyline { points, width, color, join, cap } => {
+                let placed: Vec<(f32, f32)> = points
+                    .iter()
+                    .map(|&(px, py)| (px + x as f32, py + y as f32))
+                    .collect();
+                canvas.stroke_polyline(&placed, *width, *color, *join, *cap);
+            }
+        }
+    }
+}
+
+/// Handle to a node of a `Scene`, valid until the node is removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// A shape placed in a scene
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub shape: Shape,
+    /// Where the shape's origin is placed, in user coordinates
+    pub offset: (i32, i32),
+    /// Hidden nodes stay in the scene but are not drawn
+    pub visible: bool,
+}
+
+/// A retained list of shapes that only needs redrawing when it changes
+///
+/// An alternative to drawing everything in `World::draw` every frame: build the scene once,
+/// change nodes as the world evolves, and call `render_if_dirty` from `draw`. Any mutable
+/// access to a node marks the scene dirty. Skipping a render relies on the frame buffer keeping
+/// the previous frame, so it only works when nothing else draws over the scene each frame
+/// (such as widgets, a background image or a moving overlay); otherwise call `render`
+/// unconditionally
+#[derive(Debug, Clone)]
+pub struct Scene {
+    nodes: Vec<Option<Node>>,
+    background: Option<Color>,
+    dirty: Cell<bool>,
+}
+
+impl Scene {
+    /// Create an empty scene that draws over whatever is already on the canvas
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            background: None,
+            dirty: Cell::new(true),
+        }
+    }
+
+    /// Clear the canvas to `color` before drawing the nodes
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Add a visible shape on top of all existing nodes
+    pub fn add(&mut self, shape: Shape, offset: (i32, i32)) -> NodeId {
+        self.nodes.push(Some(Node { shape, offset, visible: true }));
+        self.dirty.set(true);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Remove a node, returning it if it was still in the scene
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        let node = self.nodes.get_mut(id.0)?.take();
+        if node.is_some() {
+            self.dirty.set(true);
+        }
+        node
+    }
+
+    /// Look at a node without marking the scene dirty
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(id.0)?.as_ref()
+    }
+
+    /// Change a node, marking the scene dirty
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        let node = self.nodes.get_mut(id.0)?.as_mut()?;
+        self.dirty.set(true);
+        Some(node)
+    }
+
+    /// Move a node's origin to a new position
+    ///
+    /// Returns false if the node was removed
+    pub fn set_offset(&mut self, id: NodeId, offset: (i32, i32)) -> bool {
+        self.node_mut(id).map(|node| node.offset = offset).is_some()
+    }
+
+    /// Whether the scene changed since it was last rendered
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    /// Force the next `render_if_dirty` to draw, e.g. after something else drew over the scene
+    pub fn mark_dirty(&self) {
+        self.dirty.set(true);
+    }
+
+    /// Draw the background (if any) and all visible nodes in the order they were added
+    pub fn render(&self, canvas: &mut Canvas) {
+        if let Some(background) = self.background {
+            canvas.clear(background);
+        }
+        for node in self.nodes.iter().flatten().filter(|node| node.visible) {
+            node.shape.draw(canvas, node.offset.0, node.offset.1);
+        }
+        self.dirty.set(false);
+    }
+
+    /// Render only if the scene changed since the last render
+    ///
+    /// Returns whether anything was drawn
+    pub fn render_if_dirty(&self, canvas: &mut Canvas) -> bool {
+        let dirty = self.is_dirty();
+        if dirty {
+            self.render(canvas);
+        }
+        dirty
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,253 @@
+use crate::canvas::{Canvas, Color};
+
+/// An owned RGBA image that can be drawn onto a canvas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Sprite {
+    /// Create a sprite from its pixels in row-major order
+    ///
+    /// Panics if `pixels.len()` is not `width * height`
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            (width * height) as usize,
+            "sprite pixel count does not match its dimensions"
+        );
+        Self { width, height, pixels }
+    }
+
+    /// Create a sprite from tightly packed RGBA bytes in row-major order
+    ///
+    /// Panics if `bytes.len()` is not `width * height * 4`
+    pub fn from_rgba(width: u32, height: u32, bytes: &[u8]) -> Self {
+        assert_eq!(
+            bytes.len(),
+            (width * height * 4) as usize,
+            "sprite byte count does not match its dimensions"
+        );
+        let pixels = bytes
+            .chunks_exact(4)
+            .map(|chunk| (chunk[0], chunk[1], chunk[2], chunk[3]))
+            .collect();
+        Self { width, height, pixels }
+    }
+
+    /// Get the width of the sprite in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of the sprite in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Size of the sprite's pixels in bytes (`width * height * 4`)
+    pub fn buffer_size_bytes(&self) -> usize {
+        self.pixels.len() * 4
+    }
+
+    /// Get the pixels of the sprite in row-major order
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Get the color of a pixel (top-left origin)
+    ///
+    /// Returns None if the coordinates are out of bounds
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x < self.width && y < self.height {
+            Some(self.pixels[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    /// Get the whole sprite as a region
+    pub fn full_region(&self) -> SpriteRegion<'_> {
+        SpriteRegion {
+            sprite: self,
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Get a rectangular part of the sprite, clipped to its bounds
+    pub fn region(&self, x: u32, y: u32, width: u32, height: u32) -> SpriteRegion<'_> {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        SpriteRegion {
+            sprite: self,
+            x,
+            y,
+            width: width.min(self.width - x),
+            height: height.min(self.height - y),
+        }
+    }
+
+    /// Treat the sprite as a sheet of uniform `tile_w`×`tile_h` tiles and get tile `index`
+    ///
+    /// Tiles are numbered left to right, then top to bottom; partial tiles at the right and
+    /// bottom edges are not counted. Returns an empty region if `index` is out of range
+    pub fn tile(&self, tile_w: u32, tile_h: u32, index: u32) -> SpriteRegion<'_> {
+        let columns = self.width.checked_div(tile_w).unwrap_or(0);
+        let rows = self.height.checked_div(tile_h).unwrap_or(0);
+        if index >= columns * rows {
+            return self.region(0, 0, 0, 0);
+        }
+        self.region((index % columns) * tile_w, (index / columns) * tile_h, tile_w, tile_h)
+    }
+}
+
+/// A rectangular view into a sprite, such as one tile of a sprite sheet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteRegion<'a> {
+    sprite: &'a Sprite,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl SpriteRegion<'_> {
+    /// Get the width of the region in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height of the region in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Whether the region contains no pixels
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Get the color of a pixel relative to the region's top-left corner
+    ///
+    /// Returns None if the coordinates are outside the region
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x < self.width && y < self.height {
+            self.sprite.get_pixel(self.x + x, self.y + y)
+        } else {
+            None
+        }
+    }
+}
+
+/// A frame-by-frame animation played from the tiles of a sprite sheet
+///
+/// The world advances the animation with `update`, passing the time elapsed since the previous
+/// frame, and draws the current tile with `draw`
+#[derive(Debug, Clone)]
+pub struct AnimatedSprite {
+    sheet: Sprite,
+    tile_w: u32,
+    tile_h: u32,
+    frames: Vec<u32>,
+    fps: f32,
+    looping: bool,
+    color_key: Option<Color>,
+    elapsed: f32,
+}
+
+impl AnimatedSprite {
+    /// Create an animation showing the given tile indices of `sheet` in order at `fps` frames per second
+    ///
+    /// A looping animation starts over after its last frame; a non-looping one stays on it
+    pub fn new(sheet: Sprite, tile_w: u32, tile_h: u32, frames: Vec<u32>, fps: f32, looping: bool) -> Self {
+        Self {
+            sheet,
+            tile_w,
+            tile_h,
+            frames,
+            fps,
+            looping,
+            color_key: None,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Skip pixels equal to `color_key` when drawing
+    pub fn with_color_key(mut self, color_key: Color) -> Self {
+        self.color_key = Some(color_key);
+        self
+    }
+
+    /// Advance the animation by `dt` seconds
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed += dt.max(0.0);
+    }
+
+    /// Restart the animation from its first frame
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    /// Position of the current frame within the frame list
+    pub fn current_frame(&self) -> usize {
+        if self.frames.is_empty() || self.fps <= 0.0 {
+            return 0;
+        }
+        let frame = (self.elapsed * self.fps).floor() as usize;
+        if self.looping {
+            frame % self.frames.len()
+        } else {
+            frame.min(self.frames.len() - 1)
+        }
+    }
+
+    /// Whether a non-looping animation has reached its last frame (always false when looping)
+    pub fn is_finished(&self) -> bool {
+        !self.looping && (self.elapsed * self.fps).floor() as usize >= self.frames.len().saturating_sub(1)
+    }
+
+    /// Draw the current frame with its top-left pixel at (x, y)
+    pub fn draw(&self, canvas: &mut Canvas, x: i32, y: i32) {
+        if let Some(&index) = self.frames.get(self.current_frame()) {
+            let region = self.sheet.tile(self.tile_w, self.tile_h, index);
+            canvas.draw_sprite_region(x, y, &region, self.color_key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An animation over a 4×1 sheet of 1×1 tiles, playing tiles 3, 1 and 2 at 4 fps
+    fn animation(looping: bool) -> AnimatedSprite {
+        let sheet = Sprite::new(4, 1, vec![(0, 0, 0, 255); 4]);
+        AnimatedSprite::new(sheet, 1, 1, vec![3, 1, 2], 4.0, looping)
+    }
+
+    #[test]
+    fn animated_sprite_advances_at_its_fps() {
+        let mut looping = animation(true);
+        let mut once = animation(false);
+        // Half-frame steps of 1/8 s: each frame lasts two of them
+        let expected_looping = [0, 0, 1, 1, 2, 2, 0, 0, 1];
+        let expected_once = [0, 0, 1, 1, 2, 2, 2, 2, 2];
+        for step in 0..expected_looping.len() {
+            assert_eq!(looping.current_frame(), expected_looping[step], "looping, step {step}");
+            assert_eq!(once.current_frame(), expected_once[step], "once, step {step}");
+            assert!(!looping.is_finished());
+            assert_eq!(once.is_finished(), step >= 4, "step {step}");
+            looping.update(0.125);
+            once.update(0.125);
+        }
+
+        once.reset();
+        assert_eq!(once.current_frame(), 0);
+        assert!(!once.is_finished());
+    }
+}
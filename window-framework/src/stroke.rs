@@ -0,0 +1,205 @@
+/// How the corners between two segments of a stroked polyline are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// Extend the outer edges until they meet, falling back to `Bevel` for very sharp corners
+    #[default]
+    Miter,
+    /// Cut the corner off with a straight edge
+    Bevel,
+    /// Round the corner with a circular arc
+    Round,
+}
+
+/// How the two ends of an open stroked polyline are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    /// The stroke ends exactly at the end point
+    #[default]
+    Butt,
+    /// The stroke extends half its width past the end point
+    Square,
+    /// The stroke ends in a half circle centered on the end point
+    Round,
+}
+
+/// Miter joins longer than this many half-widths are drawn as bevels instead
+const MITER_LIMIT: f32 = 4.0;
+
+/// A convex piece of a stroke outline; the stroke is the union of all its pieces
+#[derive(Debug, Clone)]
+pub(crate) enum Piece {
+    Polygon(Vec<(f32, f32)>),
+    Circle { center: (f32, f32), radius: f32 },
+}
+
+impl Piece {
+    /// Whether a point lies inside this piece
+    pub(crate) fn contains(&self, point: (f32, f32)) -> bool {
+        match self {
+            Piece::Polygon(points) => {
+                let mut positive = false;
+                let mut negative = false;
+                for (i, &a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    let cross = (b.0 - a.0) * (point.1 - a.1) - (b.1 - a.1) * (point.0 - a.0);
+                    positive |= cross > 0.0;
+                    negative |= cross < 0.0;
+                }
+                !(positive && negative)
+            }
+            Piece::Circle { center, radius } => {
+                let dx = point.0 - center.0;
+                let dy = point.1 - center.1;
+                dx * dx + dy * dy <= radius * radius
+            }
+        }
+    }
+
+    /// Axis-aligned bounding box as (min_x, min_y, max_x, max_y)
+    pub(crate) fn bounds(&self) -> (f32, f32, f32, f32) {
+        match self {
+            Piece::Polygon(points) => points.iter().fold(
+                (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+                |(min_x, min_y, max_x, max_y), &(x, y)| {
+                    (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                },
+            ),
+            Piece::Circle { center, radius } => (
+                center.0 - radius,
+                center.1 - radius,
+                center.0 + radius,
+                center.1 + radius,
+            ),
+        }
+    }
+}
+
+/// Break the outline of a stroked polyline into convex pieces
+///
+/// A path whose first and last points coincide is treated as closed: it is joined at the seam
+/// and gets no caps
+pub(crate) fn stroke_pieces(points: &[(f32, f32)], width: f32, join: LineJoin, cap: LineCap) -> Vec<Piece> {
+    let half = width / 2.0;
+    if half <= 0.0 {
+        return Vec::new();
+    }
+
+    // Consecutive duplicates would produce zero-length segments with no direction
+    let mut path: Vec<(f32, f32)> = Vec::with_capacity(points.len());
+    for &point in points {
+        if path.last() != Some(&point) {
+            path.push(point);
+        }
+    }
+    let closed = path.len() > 2 && path.first() == path.last();
+    if closed {
+        path.pop();
+    }
+
+    let mut pieces = Vec::new();
+    match path.len() {
+        0 => return pieces,
+        1 => {
+            if cap == LineCap::Round {
+                pieces.push(Piece::Circle { center: path[0], radius: half });
+            } else if cap == LineCap::Square {
+                let (x, y) = path[0];
+                pieces.push(Piece::Polygon(vec![
+                    (x - half, y - half),
+                    (x + half, y - half),
+                    (x + half, y + half),
+                    (x - half, y + half),
+                ]));
+            }
+            return pieces;
+        }
+        _ => {}
+    }
+
+    let segment_count = if closed { path.len() } else { path.len() - 1 };
+    for i in 0..segment_count {
+        let mut a = path[i];
+        let mut b = path[(i + 1) % path.len()];
+        let dir = direction(a, b);
+        if !closed && cap == LineCap::Square {
+            if i == 0 {
+                a = (a.0 - dir.0 * half, a.1 - dir.1 * half);
+            }
+            if i == segment_count - 1 {
+                b = (b.0 + dir.0 * half, b.1 + dir.1 * half);
+            }
+        }
+        let normal = (-dir.1 * half, dir.0 * half);
+        pieces.push(Piece::Polygon(vec![
+            (a.0 + normal.0, a.1 + normal.1),
+            (b.0 + normal.0, b.1 + normal.1),
+            (b.0 - normal.0, b.1 - normal.1),
+            (a.0 - normal.0, a.1 - normal.1),
+        ]));
+    }
+
+    // Joins at every interior vertex (and at every vertex of a closed path)
+    let (first_join, last_join) = if closed { (0, path.len()) } else { (1, path.len() - 1) };
+    for i in first_join..last_join {
+        let prev = path[(i + path.len() - 1) % path.len()];
+        let point = path[i];
+        let next = path[(i + 1) % path.len()];
+        pieces.extend(join_piece(prev, point, next, half, join));
+    }
+
+    if !closed && cap == LineCap::Round {
+        pieces.push(Piece::Circle { center: path[0], radius: half });
+        pieces.push(Piece::Circle { center: path[path.len() - 1], radius: half });
+    }
+
+    pieces
+}
+
+/// The piece filling the outer corner where segments prev→point and point→next meet
+fn join_piece(prev: (f32, f32), point: (f32, f32), next: (f32, f32), half: f32, join: LineJoin) -> Option<Piece> {
+    let d0 = direction(prev, point);
+    let d1 = direction(point, next);
+    let cross = d0.0 * d1.1 - d0.1 * d1.0;
+    if cross.abs() < 1e-6 && d0.0 * d1.0 + d0.1 * d1.1 > 0.0 {
+        // Straight continuation, the segments already meet cleanly
+        return None;
+    }
+
+    if join == LineJoin::Round {
+        return Some(Piece::Circle { center: point, radius: half });
+    }
+
+    // The outer side of the corner is opposite the direction of the turn
+    let side = if cross > 0.0 { -1.0 } else { 1.0 };
+    let n0 = (-d0.1 * side, d0.0 * side);
+    let n1 = (-d1.1 * side, d1.0 * side);
+    let outer0 = (point.0 + n0.0 * half, point.1 + n0.1 * half);
+    let outer1 = (point.0 + n1.0 * half, point.1 + n1.1 * half);
+
+    if join == LineJoin::Miter {
+        let bisector = (n0.0 + n1.0, n0.1 + n1.1);
+        let length = (bisector.0 * bisector.0 + bisector.1 * bisector.1).sqrt();
+        if length > 1e-6 {
+            let bisector = (bisector.0 / length, bisector.1 / length);
+            let cos_half_angle = bisector.0 * n0.0 + bisector.1 * n0.1;
+            let miter_length = 1.0 / cos_half_angle;
+            if miter_length <= MITER_LIMIT {
+                let tip = (
+                    point.0 + bisector.0 * half * miter_length,
+                    point.1 + bisector.1 * half * miter_length,
+                );
+                return Some(Piece::Polygon(vec![point, outer0, tip, outer1]));
+            }
+        }
+    }
+
+    Some(Piece::Polygon(vec![point, outer0, outer1]))
+}
+
+/// Unit vector pointing from a to b
+fn direction(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    let dx = b.0 - a.0;
+    let dy = b.1 - a.1;
+    let length = (dx * dx + dy * dy).sqrt();
+    (dx / length, dy / length)
+}
@@ -0,0 +1,156 @@
+use crate::canvas::{lerp_color, Color};
+
+/// Values that can be interpolated linearly, as animated by a `Timeline`
+pub trait Lerp {
+    /// The value a fraction `t` of the way from `self` to `other` (0 gives `self`, 1 `other`)
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// Points and vectors, interpolated component by component
+impl Lerp for (f32, f32) {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        (self.0.lerp(&other.0, t), self.1.lerp(&other.1, t))
+    }
+}
+
+/// Colors, interpolated channel by channel and rounded
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        lerp_color(*self, *other, t)
+    }
+}
+
+/// How the progress through a timeline segment is shaped before interpolating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Start slowly and speed up (quadratic)
+    EaseIn,
+    /// Start quickly and slow down (quadratic)
+    EaseOut,
+    /// Slow at both ends, fastest in the middle (cubic smoothstep)
+    EaseInOut,
+    /// Hold the previous value until the segment ends, then jump
+    Step,
+}
+
+impl Easing {
+    /// Map linear progress `t` from 0 to 1 onto eased progress, also from 0 to 1
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Easing::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
+}
+
+/// A point on a timeline: the value reached at `time`, and the easing used on the way there
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    pub easing: Easing,
+}
+
+/// A declarative animation of one value through keyframes sorted by time
+///
+/// A world keeps its own elapsed time, advances it every update (by a fixed step, or by
+/// `FrameStats::frame_time` for wall-clock animation) and reads the animated values with
+/// `sample`. Times are in whatever unit the world advances them by, usually seconds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timeline<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T: Lerp + Clone> Timeline<T> {
+    /// Create a timeline holding `value` from `time` on
+    pub fn new(time: f32, value: T) -> Self {
+        Self {
+            keyframes: vec![Keyframe { time, value, easing: Easing::Linear }],
+        }
+    }
+
+    /// Add a keyframe reached at `time`, interpolated towards with `easing`, and return the timeline
+    pub fn with_keyframe(mut self, time: f32, value: T, easing: Easing) -> Self {
+        self.add_keyframe(time, value, easing);
+        self
+    }
+
+    /// Add a keyframe reached at `time`, interpolated towards with `easing`
+    ///
+    /// Keyframes are kept sorted by time. One added at the same time as an existing keyframe
+    /// goes after it, so the value jumps there
+    pub fn add_keyframe(&mut self, time: f32, value: T, easing: Easing) {
+        let index = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        self.keyframes.insert(index, Keyframe { time, value, easing });
+    }
+
+    /// The keyframes, sorted by time
+    pub fn keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+
+    /// Time of the last keyframe, after which the value stays constant
+    pub fn end_time(&self) -> f32 {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+
+    /// The value at time `elapsed`
+    ///
+    /// Between two keyframes the value is interpolated with the later keyframe's easing, and
+    /// at a keyframe's time it is exactly that keyframe's value. Before the first keyframe and
+    /// after the last the value holds at the nearest one
+    pub fn sample(&self, elapsed: f32) -> T {
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= elapsed);
+        if next == 0 {
+            return self.keyframes[0].value.clone();
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1].value.clone();
+        }
+        let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        let progress = (elapsed - from.time) / (to.time - from.time);
+        from.value.lerp(&to.value, to.easing.apply(progress))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_at_a_keyframe_time_is_that_keyframe() {
+        let timeline = Timeline::new(0.1, 0.3f32)
+            .with_keyframe(0.7, 0.1, Easing::EaseIn)
+            .with_keyframe(1.3, 0.9, Easing::Step)
+            .with_keyframe(2.9, -0.2, Easing::EaseInOut);
+        for keyframe in timeline.keyframes() {
+            assert_eq!(timeline.sample(keyframe.time), keyframe.value, "at {}", keyframe.time);
+        }
+        assert_eq!(timeline.sample(-1.0), 0.3);
+        assert_eq!(timeline.sample(5.0), -0.2);
+
+        // Of two keyframes at the same time, the later one wins there
+        let jump = Timeline::new(0.0, (0, 0, 0, 255)).with_keyframe(1.0, (255, 0, 0, 255), Easing::Linear);
+        let jump = jump.with_keyframe(1.0, (0, 0, 255, 255), Easing::Linear);
+        assert_eq!(jump.sample(1.0), (0, 0, 255, 255));
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::world::TimeSource;
+
+/// A frame is considered stalled when it takes this many times the target interval
+const STALL_THRESHOLD: f32 = 1.5;
+
+/// Number of recent frames used to compute `FrameStats::recent_drop_rate`
+const RECENT_WINDOW: usize = 120;
+
+/// Frame rate used by `TargetFps::Auto` when the monitor doesn't report its refresh rate
+const FALLBACK_FPS: u32 = 60;
+
+/// The frame rate the render loop is paced to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetFps {
+    /// A fixed number of frames per second
+    Fixed(u32),
+    /// The refresh rate of the monitor the window is on, or 60 when it is unknown
+    ///
+    /// Re-detected when the window moves, so it follows the window between displays
+    Auto,
+}
+
+impl TargetFps {
+    /// The frame rate to use given the monitor's refresh rate in millihertz, if known
+    pub fn resolve(self, refresh_rate_millihertz: Option<u32>) -> u32 {
+        match self {
+            TargetFps::Fixed(fps) => fps,
+            TargetFps::Auto => refresh_rate_millihertz
+                .map(|millihertz| (millihertz + 500) / 1000)
+                .filter(|&fps| fps > 0)
+                .unwrap_or(FALLBACK_FPS),
+        }
+    }
+}
+
+impl From<u32> for TargetFps {
+    fn from(fps: u32) -> Self {
+        TargetFps::Fixed(fps)
+    }
+}
+
+/// Timing information about the frame being rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    /// Number of frames rendered so far, including this one
+    pub frame_count: u64,
+    /// Time since the previous frame started (zero for the first frame)
+    pub frame_time: Duration,
+    /// Time since the first frame started, excluding time spent minimized
+    pub elapsed: Duration,
+    /// Interval between frames expected at the configured target frame rate
+    pub target_interval: Duration,
+    /// Total number of frames missed since startup
+    ///
+    /// A frame taking more than 1.5× the target interval counts as a stall, and every whole target
+    /// interval it spans beyond the first is counted as a dropped frame (at least one per stall)
+    pub dropped_frames: u64,
+    /// Fraction (0.0-1.0) of the last 120 frames that stalled
+    pub recent_drop_rate: f32,
+    /// Time the previous frame spent in `update` (all of its fast-forward steps together)
+    pub update_time: Duration,
+    /// Time the previous frame spent drawing, from the background through the grid
+    pub draw_time: Duration,
+}
+
+/// Tracks frame cadence against a target frame rate
+pub(crate) struct FrameTimer {
+    target_interval: Duration,
+    last_frame: Option<Duration>,
+    elapsed: Duration,
+    frame_count: u64,
+    dropped_frames: u64,
+    recent_stalls: VecDeque<bool>,
+    update_time: Duration,
+    draw_time: Duration,
+}
+
+impl FrameTimer {
+    pub(crate) fn new(target_fps: u32) -> Self {
+        Self {
+            target_interval: interval_for(target_fps),
+            last_frame: None,
+            elapsed: Duration::ZERO,
+            frame_count: 0,
+            dropped_frames: 0,
+            recent_stalls: VecDeque::with_capacity(RECENT_WINDOW),
+            update_time: Duration::ZERO,
+            draw_time: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn set_target_fps(&mut self, target_fps: u32) {
+        self.target_interval = interval_for(target_fps);
+    }
+
+    /// Interval between frames at the current target frame rate
+    pub(crate) fn target_interval(&self) -> Duration {
+        self.target_interval
+    }
+
+    /// Record how long the current frame's phases took, reported with the next frame's stats
+    pub(crate) fn record_phases(&mut self, update_time: Duration, draw_time: Duration) {
+        self.update_time = update_time;
+        self.draw_time = draw_time;
+    }
+
+    /// Forget when the last frame started, so a deliberate gap isn't counted as dropped frames
+    pub(crate) fn pause(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// Record the start of a new frame and return the updated statistics
+    pub(crate) fn tick(&mut self, now: Duration) -> FrameStats {
+        let frame_time = self
+            .last_frame
+            .map(|last| now.saturating_sub(last))
+            .unwrap_or_default();
+        self.last_frame = Some(now);
+        self.elapsed += frame_time;
+        self.frame_count += 1;
+
+        let ratio = frame_time.as_secs_f32() / self.target_interval.as_secs_f32();
+        let stalled = ratio > STALL_THRESHOLD;
+        if stalled {
+            self.dropped_frames += (ratio.round() as u64).saturating_sub(1).max(1);
+        }
+        if self.recent_stalls.len() == RECENT_WINDOW {
+            self.recent_stalls.pop_front();
+        }
+        self.recent_stalls.push_back(stalled);
+        let recent_drops = self.recent_stalls.iter().filter(|&&stalled| stalled).count();
+
+        FrameStats {
+            frame_count: self.frame_count,
+            frame_time,
+            elapsed: self.elapsed,
+            target_interval: self.target_interval,
+            dropped_frames: self.dropped_frames,
+            recent_drop_rate: recent_drops as f32 / self.recent_stalls.len() as f32,
+            update_time: self.update_time,
+            draw_time: self.draw_time,
+        }
+    }
+}
+
+/// The time frames are measured against: a `TimeSource` if one was configured, otherwise the
+/// system clock
+pub(crate) struct Clock {
+    source: Option<TimeSource>,
+    origin: Instant,
+}
+
+impl Clock {
+    pub(crate) fn new(source: Option<TimeSource>) -> Self {
+        Self {
+            source,
+            origin: Instant::now(),
+        }
+    }
+
+    /// Current time as the duration since the clock's origin
+    pub(crate) fn now(&mut self) -> Duration {
+        match self.source.as_mut() {
+            Some(source) => source(),
+            None => self.origin.elapsed(),
+        }
+    }
+}
+
+fn interval_for(target_fps: u32) -> Duration {
+    Duration::from_secs_f64(1.0 / target_fps.max(1) as f64)
+}
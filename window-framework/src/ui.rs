@@ -0,0 +1,156 @@
+use crate::canvas::{point_in_circle, Canvas, Color, CoordinateSystem, Rect};
+use crate::input::InputState;
+
+const BACKGROUND: Color = (0x30, 0x30, 0x38, 0xff);
+const BACKGROUND_HOVER: Color = (0x48, 0x48, 0x54, 0xff);
+const BACKGROUND_ACTIVE: Color = (0x5e, 0x48, 0xe8, 0xff);
+const BORDER: Color = (0x90, 0x90, 0xa0, 0xff);
+const TEXT: Color = (0xff, 0xff, 0xff, 0xff);
+
+/// A minimal immediate-mode widget layer
+///
+/// Built fresh every frame by `App` and passed to `World::ui`. Widgets are drawn as they are
+/// declared and report interaction right away, so no widget state is kept between frames; the
+/// world owns every value a widget edits. Hit testing uses the logical pixel under the mouse
+/// (see `InputState::picked_pixel`).
+pub struct Ui<'c, 'a> {
+    canvas: &'c mut Canvas<'a>,
+    input: &'c InputState,
+}
+
+impl<'c, 'a> Ui<'c, 'a> {
+    /// Create a widget layer drawing to `canvas` and reading `input`
+    pub fn new(canvas: &'c mut Canvas<'a>, input: &'c InputState) -> Self {
+        Self { canvas, input }
+    }
+
+    /// Access the underlying canvas, e.g. to draw decorations between widgets
+    pub fn canvas(&mut self) -> &mut Canvas<'a> {
+        self.canvas
+    }
+
+    /// Whether the mouse cursor is over the given rectangle
+    pub fn is_hovered(&self, rect: Rect) -> bool {
+        self.input
+            .picked_pixel()
+            .is_some_and(|(x, y)| rect.contains(x, y))
+    }
+
+    /// Whether the mouse cursor is over the circle `Canvas::fill_circle` would fill
+    pub fn is_hovered_circle(&self, cx: i32, cy: i32, radius: u32) -> bool {
+        self.input
+            .picked_pixel()
+            .is_some_and(|(x, y)| point_in_circle(x, y, cx, cy, radius))
+    }
+
+    /// Draw a text label with its top-left pixel at (x, y)
+    pub fn label(&mut self, x: i32, y: i32, text: &str) {
+        self.canvas.draw_text(x, y, text, TEXT);
+    }
+
+    /// Draw a push button with a centered label
+    ///
+    /// Returns true on the frame the left mouse button is pressed over it
+    pub fn button(&mut self, rect: Rect, label: &str) -> bool {
+        let hovered = self.is_hovered(rect);
+        let clicked = hovered && self.input.is_left_mouse_just_pressed();
+
+        let background = if hovered && self.input.is_left_mouse_pressed() {
+            BACKGROUND_ACTIVE
+        } else if hovered {
+            BACKGROUND_HOVER
+        } else {
+            BACKGROUND
+        };
+        self.panel(rect, background);
+
+        let text_x = rect.x + (rect.width as i32 - self.canvas.text_width(label) as i32) / 2;
+        let text_y = self.visual_top(rect) + self.down() * (rect.height as i32 - self.canvas.text_height(label) as i32) / 2;
+        self.canvas.draw_text(text_x, text_y, label, TEXT);
+
+        clicked
+    }
+
+    /// Draw a round push button of `radius` around (cx, cy) with a centered label
+    ///
+    /// Hit testing follows the drawn circle (see `point_in_circle`), so clicks in the corners of
+    /// its bounding box don't count. Returns true on the frame the left mouse button is pressed
+    /// over it
+    pub fn circle_button(&mut self, cx: i32, cy: i32, radius: u32, label: &str) -> bool {
+        let hovered = self.is_hovered_circle(cx, cy, radius);
+        let clicked = hovered && self.input.is_left_mouse_just_pressed();
+
+        let background = if hovered && self.input.is_left_mouse_pressed() {
+            BACKGROUND_ACTIVE
+        } else if hovered {
+            BACKGROUND_HOVER
+        } else {
+            BACKGROUND
+        };
+        self.canvas.fill_circle(cx, cy, radius, background);
+        self.canvas.draw_circle(cx, cy, radius, BORDER);
+
+        let text_x = cx - self.canvas.text_width(label) as i32 / 2;
+        let text_y = cy - self.down() * (self.canvas.text_height(label) as i32 / 2);
+        self.canvas.draw_text(text_x, text_y, label, TEXT);
+
+        clicked
+    }
+
+    /// Draw a horizontal slider and return its value after this frame's interaction
+    ///
+    /// While the left mouse button is held over the slider the value follows the cursor;
+    /// otherwise `value` is returned clamped to `min..=max`
+    pub fn slider(&mut self, rect: Rect, value: f32, min: f32, max: f32) -> f32 {
+        let mut value = value.clamp(min.min(max), max.max(min));
+        if self.input.is_left_mouse_pressed() {
+            if let Some((x, y)) = self.input.picked_pixel() {
+                if rect.contains(x, y) && rect.width > 1 {
+                    let t = (x - rect.x) as f32 / (rect.width - 1) as f32;
+                    value = min + (max - min) * t;
+                }
+            }
+        }
+
+        let background = if self.is_hovered(rect) { BACKGROUND_HOVER } else { BACKGROUND };
+        self.panel(rect, background);
+
+        let t = if max == min { 0.0 } else { (value - min) / (max - min) };
+        let handle_x = rect.x + (t * rect.width.saturating_sub(1) as f32).round() as i32;
+        let handle_width = 3.min(rect.width);
+        let handle_x = handle_x.min(rect.x + (rect.width - handle_width) as i32);
+        self.canvas.fill_rect(handle_x, rect.y, handle_width, rect.height, BACKGROUND_ACTIVE);
+
+        value
+    }
+
+    /// Fill a rectangle and outline it with the border color
+    fn panel(&mut self, rect: Rect, background: Color) {
+        self.canvas.fill_rect(rect.x, rect.y, rect.width, rect.height, background);
+        if rect.width == 0 || rect.height == 0 {
+            return;
+        }
+        let right = rect.x + rect.width as i32 - 1;
+        let far_y = rect.y + rect.height as i32 - 1;
+        self.canvas.fill_rect(rect.x, rect.y, rect.width, 1, BORDER);
+        self.canvas.fill_rect(rect.x, far_y, rect.width, 1, BORDER);
+        self.canvas.fill_rect(rect.x, rect.y, 1, rect.height, BORDER);
+        self.canvas.fill_rect(right, rect.y, 1, rect.height, BORDER);
+    }
+
+    /// The user y coordinate of the visual top row of a rectangle
+    fn visual_top(&self, rect: Rect) -> i32 {
+        match self.canvas.coordinate_system() {
+            CoordinateSystem::TopLeft => rect.y,
+            CoordinateSystem::Center => rect.y + rect.height as i32 - 1,
+        }
+    }
+
+    /// The y step in user coordinates that moves one pixel visually downward
+    fn down(&self) -> i32 {
+        match self.canvas.coordinate_system() {
+            CoordinateSystem::TopLeft => 1,
+            CoordinateSystem::Center => -1,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::headless::render_frames;
+use crate::world::World;
+
+/// Render a world headlessly and encode the frames into an H.264 MP4 file
+///
+/// Frames are piped as raw RGBA into an `ffmpeg` process, which must be available on the `PATH`.
+/// Frames are rendered on a fixed clock of `1 / fps` seconds each (see `render_frames`), so the
+/// output plays back at the world's own pace regardless of how long rendering takes. Odd
+/// dimensions are padded by one pixel because H.264 requires even sizes.
+///
+/// Returns an error if ffmpeg cannot be started, stops accepting frames, or exits unsuccessfully
+pub fn render_to_mp4<W: World>(frames: u32, fps: u32, path: impl AsRef<Path>) -> io::Result<()> {
+    let config = W::config();
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pixel_format", "rgba"])
+        .arg("-video_size")
+        .arg(format!("{}x{}", config.width, config.height))
+        .arg("-framerate")
+        .arg(fps.max(1).to_string())
+        .args(["-i", "-", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+        .arg(path.as_ref())
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    let mut stdin = ffmpeg.stdin.take().expect("ffmpeg stdin is piped");
+    let result = render_frames::<W, _>(frames, fps, |frame, _, _| stdin.write_all(frame));
+    // Close the pipe so ffmpeg finishes the file, even if rendering failed part way
+    drop(stdin);
+    let status = ffmpeg.wait()?;
+    result?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("ffmpeg exited with {status}")))
+    }
+}
@@ -1,7 +1,24 @@
-use crate::canvas::{Canvas, CoordinateSystem};
+#[cfg(feature = "hot-reload")]
+use std::collections::HashMap;
+use std::time::Duration;
+#[cfg(feature = "hot-reload")]
+use std::path::PathBuf;
+
+use crate::canvas::{Canvas, Color, CoordinateSystem, GridStyle};
+use crate::event::FrameworkEvent;
 use crate::input::InputState;
+use crate::sprite::Sprite;
+use crate::timing::{FrameStats, TargetFps};
+use crate::ui::Ui;
+
+/// Callback receiving each rendered frame as physical RGBA bytes with its width and height
+pub type FrameSink = Box<dyn FnMut(&[u8], u32, u32)>;
+
+/// Callback returning the current time as the duration since an arbitrary fixed origin
+pub type TimeSource = Box<dyn FnMut() -> Duration>;
 
 /// Configuration for a World implementation
+#[derive(Debug, Clone)]
 pub struct WorldConfig {
     pub width: u32,
     pub height: u32,
@@ -10,7 +27,57 @@ pub struct WorldConfig {
     pub pixel_grid_width: u32,
     pub pixel_grid_height: u32,
     pub show_grid: bool,
-    pub grid_color: (u8, u8, u8, u8),
+    pub grid_color: Color,
+    /// Whether the grid is drawn as full lines or as dots at their crossings (default lines)
+    pub grid_style: GridStyle,
+    /// Initial window position in physical screen coordinates (None lets the OS decide)
+    pub position: Option<(i32, i32)>,
+    /// Initial inner size of the window in physical pixels (None uses the platform default)
+    ///
+    /// See `with_window_size`
+    pub window_size: Option<(u32, u32)>,
+    /// Frame rate the render loop is paced to, also used to detect dropped frames
+    pub target_fps: TargetFps,
+    /// Width:height ratio of a logical pixel (1.0 = square)
+    ///
+    /// Applied on top of the `pixel_grid_width`/`pixel_grid_height` mapping. At 1.0 each axis is
+    /// scaled independently, so blocks are only square when the grid matches the window's shape;
+    /// with any other aspect the largest block of that shape that fits the grid into
+    /// `width`×`height` is used, so pick the grid size to match (e.g. a 2.0 aspect wants half as
+    /// many columns)
+    pub pixel_aspect: f32,
+    /// Interpolate smoothly between logical pixels instead of drawing them as solid blocks
+    ///
+    /// Off by default to keep the crisp pixel-art look. See `Canvas::smooth_upscale_into`
+    pub smooth_upscale: bool,
+    /// Number of times `update` runs before each `draw` (1 = normal speed)
+    pub updates_per_frame: u32,
+    /// Log a warning whenever `update` or drawing takes longer than this many milliseconds
+    ///
+    /// None (the default) disables the check. See `with_frame_budget_ms`
+    pub frame_budget_ms: Option<f32>,
+    /// Force every presented pixel to be fully opaque, whatever alpha the world drew with
+    ///
+    /// On by default. See `with_opaque`
+    pub opaque: bool,
+    /// Open a see-through window where pixels with alpha 0 show what is behind it
+    ///
+    /// Off by default. See `with_transparent`
+    pub transparent: bool,
+    /// Keep calling `handle_input` and `update` while the window is minimized or hidden
+    ///
+    /// Drawing and presenting always pause while minimized. See `with_update_while_minimized`
+    pub update_while_minimized: bool,
+    /// File to load settings from at startup and reload whenever it changes
+    ///
+    /// See `with_config_file`
+    #[cfg(feature = "hot-reload")]
+    pub config_file: Option<PathBuf>,
+    /// Values from the config file for keys the framework doesn't recognize
+    ///
+    /// Empty unless loaded from a file; lets a world tune its own parameters live
+    #[cfg(feature = "hot-reload")]
+    pub params: HashMap<String, String>,
 }
 
 impl WorldConfig {
@@ -18,6 +85,7 @@ impl WorldConfig {
     ///
     /// If pixel_grid_width or pixel_grid_height are None, they default to width and height respectively (1:1 pixel mapping)
     /// If show_grid is true, grid lines will be drawn between logical pixels (only visible when pixel grid is smaller than canvas)
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         width: u32,
         height: u32,
@@ -26,7 +94,7 @@ impl WorldConfig {
         pixel_grid_width: Option<u32>,
         pixel_grid_height: Option<u32>,
         show_grid: bool,
-        grid_color: (u8, u8, u8, u8),
+        grid_color: Color,
     ) -> Self {
         Self {
             width,
@@ -37,8 +105,150 @@ impl WorldConfig {
             pixel_grid_height: pixel_grid_height.unwrap_or(height),
             show_grid,
             grid_color,
+            grid_style: GridStyle::Lines,
+            position: None,
+            window_size: None,
+            target_fps: TargetFps::Fixed(60),
+            pixel_aspect: 1.0,
+            smooth_upscale: false,
+            updates_per_frame: 1,
+            frame_budget_ms: None,
+            opaque: true,
+            transparent: false,
+            update_while_minimized: false,
+            #[cfg(feature = "hot-reload")]
+            config_file: None,
+            #[cfg(feature = "hot-reload")]
+            params: HashMap::new(),
         }
     }
+
+    /// Open the window at the given physical screen coordinates
+    ///
+    /// Positions outside every monitor are passed through unchanged; the OS clamps or ignores them
+    pub fn with_position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Open the window with an inner size of `width`×`height` physical pixels
+    ///
+    /// Three sizes are involved: `width`×`height` is the frame buffer the world draws into,
+    /// `pixel_grid_width`×`pixel_grid_height` is the logical grid spread over that buffer, and
+    /// the window size is how large the buffer is shown on screen. The buffer is scaled into the
+    /// window by the largest whole factor that fits and centered, with the rest left as borders,
+    /// so a window size equal to `width`×`height` shows every buffer pixel 1:1. Without this the
+    /// platform picks the initial size, which makes the on-screen scale of the grid depend on
+    /// the OS. Users can still resize the window afterwards
+    pub fn with_window_size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    /// Set the frame rate the render loop runs at (default 60)
+    ///
+    /// Accepts a plain number of frames per second or `TargetFps::Auto` to match the monitor.
+    /// Between frames the loop sleeps until the next one is due rather than drawing as fast as it
+    /// can; a frame that runs late moves the schedule back instead of being made up for. The
+    /// same rate is the reference for `FrameStats::dropped_frames`
+    pub fn with_target_fps(mut self, target_fps: impl Into<TargetFps>) -> Self {
+        self.target_fps = target_fps.into();
+        self
+    }
+
+    /// Draw the grid as lines or as dots at their crossings (default `GridStyle::Lines`)
+    pub fn with_grid_style(mut self, grid_style: GridStyle) -> Self {
+        self.grid_style = grid_style;
+        self
+    }
+
+    /// Set the width:height ratio of a logical pixel (default 1.0)
+    pub fn with_pixel_aspect(mut self, pixel_aspect: f32) -> Self {
+        self.pixel_aspect = pixel_aspect;
+        self
+    }
+
+    /// Enable or disable bilinear upscaling of the pixel grid (default false)
+    pub fn with_smooth_upscale(mut self, smooth_upscale: bool) -> Self {
+        self.smooth_upscale = smooth_upscale;
+        self
+    }
+
+    /// Run `update` several times per drawn frame to fast-forward a simulation (default 1)
+    pub fn with_updates_per_frame(mut self, updates_per_frame: u32) -> Self {
+        self.updates_per_frame = updates_per_frame;
+        self
+    }
+
+    /// Warn when a phase of the frame overruns a time budget in milliseconds (default disabled)
+    ///
+    /// `update` (all fast-forward steps together) and drawing are timed separately and each is
+    /// compared against the whole budget; an overrun logs a warning naming the phase and by how
+    /// much it went over. The split timings reach `World::on_frame_stats` either way, as
+    /// `FrameStats::update_time` and `FrameStats::draw_time`
+    pub fn with_frame_budget_ms(mut self, frame_budget_ms: f32) -> Self {
+        self.frame_budget_ms = Some(frame_budget_ms);
+        self
+    }
+
+    /// Choose whether the window's frames are forced opaque before presenting (default true)
+    ///
+    /// Some compositors show pixels with alpha below 255 as see-through, so by default the alpha
+    /// channel of each finished frame is set to 255 before it is presented (and before it reaches
+    /// a frame sink). Since the frame buffer persists, the next frame then starts from opaque
+    /// pixels. Pass false to keep the alpha the world drew, e.g. for a transparent window.
+    /// Headless rendering never touches alpha
+    pub fn with_opaque(mut self, opaque: bool) -> Self {
+        self.opaque = opaque;
+        self
+    }
+
+    /// Open a transparent window, for overlays that float over other applications (default false)
+    ///
+    /// The window is created transparent and the surface is cleared to transparent black, so
+    /// pixels the world leaves at alpha 0 show the desktop and partly transparent pixels blend
+    /// with it. This implies `with_opaque(false)` for the window's frames.
+    ///
+    /// Support depends on the platform and compositor: the window attribute is only a request,
+    /// and the GPU surface uses whatever alpha mode the platform offers first. Where either is
+    /// unsupported the window simply stays opaque, with transparent areas shown black
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Keep the simulation running while the window is minimized (default false)
+    ///
+    /// While the window is minimized or fully hidden nothing is drawn or presented. By default
+    /// the world is paused as well and resumes where it left off when the window is restored.
+    /// With this enabled, `handle_input` and `update` keep being called at the target frame
+    /// rate instead, but `draw`, `ui` and `on_frame_stats` are not
+    pub fn with_update_while_minimized(mut self, update_while_minimized: bool) -> Self {
+        self.update_while_minimized = update_while_minimized;
+        self
+    }
+
+    /// Load settings from a file and reload them whenever it changes while running
+    ///
+    /// The file is applied on top of this config (see `parse_config` for the format) at startup
+    /// and each time its modification time changes, which is checked once per frame. Every
+    /// successful load is passed to `World::on_config_reload`; the frame rate, pixel aspect,
+    /// smoothing, grid visibility and color, updates per frame, frame budget and title are also
+    /// updated automatically (grid visibility and updates per frame only while the World hooks
+    /// for them return None). A file
+    /// that fails to read or parse is logged and the previous settings are kept
+    #[cfg(feature = "hot-reload")]
+    pub fn with_config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+}
+
+/// Information about the environment passed to `World::on_start`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartContext {
+    /// Size of the primary monitor in physical pixels (None when no monitor is available, e.g. headless)
+    pub monitor_size: Option<(u32, u32)>,
 }
 
 /// Trait that defines the interface for a visualization world
@@ -49,14 +259,126 @@ pub trait World: Sized {
     /// Get the configuration for this world (window size, title, etc.)
     fn config() -> WorldConfig;
 
-    /// Update the world state (called once per frame)
+    /// Update the world state (called once per frame, or `updates_per_frame` times when fast-forwarding)
     fn update(&mut self);
 
     /// Draw the world state to the canvas
     fn draw(&self, canvas: &mut Canvas);
 
+    /// Image the canvas is reset to at the start of every frame, before `draw`
+    ///
+    /// Lets a world annotate a photo or map without redrawing it: the image is stretched over
+    /// the pixel grid (see `Canvas::draw_background_image`) and `draw` composites on top.
+    /// Default implementation returns None, leaving the previous frame in place
+    fn background_image(&self) -> Option<&Sprite> {
+        None
+    }
+
+    /// Declare immediate-mode widgets (called once per frame after draw)
+    ///
+    /// Widgets are drawn on top of the world and report clicks and edits immediately, so this
+    /// is where a world reacts to them. Default implementation does nothing
+    fn ui(&mut self, _ui: &mut Ui) {}
+
+    /// Whether grid lines should be drawn this frame, overriding the config
+    ///
+    /// Queried once per frame, so a world can toggle the grid at runtime by overriding this.
+    /// None keeps the `show_grid` value from `config`, which is read once at startup and again
+    /// on every config file reload. Default implementation returns None
+    fn show_grid(&self) -> Option<bool> {
+        None
+    }
+
+    /// Called once after the window and world have been created, before the first frame
+    ///
+    /// Headless rendering (`render_frames`) calls it too, with no monitor size.
+    ///
+    /// Default implementation does nothing
+    fn on_start(&mut self, _context: &StartContext) {}
+
+    /// Stream every rendered frame to a callback, e.g. to pipe it into ffmpeg or a websocket
+    ///
+    /// Called exactly once, right after `on_start`, so the sink can own resources like a child
+    /// process that should only be created once. The sink receives the physical RGBA buffer
+    /// (including widgets and grid lines) with its width and height once per frame, after
+    /// drawing and before presenting; headless rendering feeds it too. It runs on the render
+    /// thread, so heavy work in it stalls the render loop; hand frames off to another thread if
+    /// encoding or sending is slow. Default implementation returns None
+    fn frame_sink(&mut self) -> Option<FrameSink> {
+        None
+    }
+
+    /// Measure frame timing against a custom clock instead of the system clock
+    ///
+    /// Called exactly once, right after `frame_sink`, so the source can keep its own state. It
+    /// returns the current time as the duration since any fixed origin and must never go
+    /// backwards. `FrameStats::frame_time` and `FrameStats::elapsed` are derived from it, so a
+    /// source that stops advancing pauses time for the world and one that jumps steps it forward,
+    /// e.g. to drive the world from a recorded replay or an external sync signal. The frame loop
+    /// itself still runs at the target rate in real time, and the phase timings (`update_time`,
+    /// `draw_time`) stay on the system clock since they measure real cost. Headless rendering
+    /// (`render_frames` and the recorders built on it) always runs on its own fixed clock of
+    /// `1 / fps` per frame and never calls this. Default implementation returns None
+    fn time_source(&mut self) -> Option<TimeSource> {
+        None
+    }
+
+    /// Receive timing statistics for the current frame (called once per frame before handle_input)
+    ///
+    /// Default implementation does nothing
+    fn on_frame_stats(&mut self, _stats: &FrameStats) {}
+
+    /// Receive settings loaded from the config file (see `WorldConfig::with_config_file`)
+    ///
+    /// Called after `on_start` when the file is first loaded and again after every change.
+    /// `show_grid` and `updates_per_frame` are applied automatically unless the World hooks of
+    /// the same names override them. Default implementation does nothing
+    #[cfg(feature = "hot-reload")]
+    fn on_config_reload(&mut self, _config: &WorldConfig) {}
+
+    /// Observe everything the framework does, for logging and debugging
+    ///
+    /// Called as window events arrive and after each presented frame; see `FrameworkEvent`.
+    /// Events before the world exists (such as a failure to create the surface) are only
+    /// logged. Default implementation does nothing
+    fn on_event(&mut self, _event: &FrameworkEvent) {}
+
+    /// How many times `update` should run before the next `draw`, overriding the config
+    ///
+    /// Queried once per frame, so a world can speed up or slow down at runtime by overriding
+    /// this. Each call to `update` is one simulation step; there is no delta time, so a step
+    /// covers the same amount of simulated time at any speed. Values below 1 are treated as 1.
+    /// None keeps the `updates_per_frame` value from `config`, which is read once at startup and
+    /// again on every config file reload. Default implementation returns None
+    fn updates_per_frame(&self) -> Option<u32> {
+        None
+    }
+
+    /// Bytes the world keeps in its own pixel buffers, such as off-screen canvases, sprites and
+    /// recorded frames
+    ///
+    /// Added to the framework's buffers in `InputState::buffer_size_bytes`, so a world that
+    /// reports them here gets a single estimate of what it costs. `OwnedCanvas` and `Sprite`
+    /// have `buffer_size_bytes` to sum up. Default implementation returns 0
+    fn buffer_size_bytes(&self) -> usize {
+        0
+    }
+
     /// Handle input events (called once per frame before update)
     ///
     /// Default implementation does nothing
     fn handle_input(&mut self, _input: &InputState) {}
+
+    /// Whether typed text should take priority over key bindings, as while a text field is focused
+    ///
+    /// Queried for every key press. While it returns true, a key that types text (letters,
+    /// digits, punctuation, Space) is only added to `InputState::text_input`, and not to
+    /// `keys_pressed`, `keys_just_pressed`, `keys_repeated` or `logical_keys_pressed`, so
+    /// navigation bindings on those keys don't fire while typing. Keys that type nothing or
+    /// only a control character (arrows, Enter, Backspace, Escape, modifiers, Ctrl shortcuts)
+    /// are recorded as usual. Text is collected in `text_input` either way.
+    /// Default implementation returns false
+    fn captures_text_input(&self) -> bool {
+        false
+    }
 }